@@ -1,11 +1,39 @@
 use std::io;
 
+struct FuelFormula {
+    divisor: i64,
+    offset: i64,
+}
+
+impl FuelFormula {
+    fn fuel_for(&self, mass: i64) -> i64 {
+        (mass / self.divisor - self.offset).max(0)
+    }
+
+    // Fuel has mass too, so keep fuelling the last batch of fuel until it needs none
+    fn total_fuel_for(&self, mass: i64) -> i64 {
+        let mut total = 0;
+        let mut fuel = self.fuel_for(mass);
+
+        while fuel > 0 {
+            total += fuel;
+            fuel = self.fuel_for(fuel);
+        }
+
+        total
+    }
+}
+
 fn main() {
     println!("{}", solve(true));
 }
 
-fn solve(is_v2: bool) -> u32 {
-    let mut total: u32 = 0;
+fn solve(is_v2: bool) -> i64 {
+    let formula = FuelFormula {
+        divisor: 3,
+        offset: 2,
+    };
+    let mut total = 0;
 
     loop {
         let line = read_line().unwrap();
@@ -14,26 +42,15 @@ fn solve(is_v2: bool) -> u32 {
             break;
         }
 
-        let mass: u32 = line.parse().unwrap();
-        let new_fuel = get_module_fuel(mass);
-        total += new_fuel;
-
-        if is_v2 {
-            let mut fuel_to_fuel = new_fuel;
-
-            while fuel_to_fuel > 8 {
-                let fuel_fuel = get_module_fuel(fuel_to_fuel);
-                total += fuel_fuel;
-                fuel_to_fuel = fuel_fuel;
-            }
-        }
+        let mass: i64 = line.parse().unwrap();
+        total += if is_v2 {
+            formula.total_fuel_for(mass)
+        } else {
+            formula.fuel_for(mass)
+        };
     }
 
-    return total;
-}
-
-fn get_module_fuel(mass: u32) -> u32 {
-    (mass / 3) - 2
+    total
 }
 
 fn read_line() -> Result<String, io::Error> {
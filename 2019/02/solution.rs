@@ -1,42 +1,165 @@
 use std::io;
+use std::ops::RangeInclusive;
 
-fn main() {
-    println!("{}", solve(true));
+// Memory cells to overwrite before each run, and the values each may take
+struct InputSearch<'a> {
+    program: &'a [i64],
+    cells: Vec<usize>,
+    bounds: Vec<RangeInclusive<i64>>,
 }
 
-fn solve(is_v2: bool) -> usize {
-    let line = read_line().unwrap();
-    let mut nums: Vec<usize> = line
-        .split(",")
-        .map(|i| i.parse::<usize>().unwrap())
-        .collect();
+impl<'a> InputSearch<'a> {
+    fn new(program: &'a [i64], cells: Vec<usize>, bounds: Vec<RangeInclusive<i64>>) -> Self {
+        Self {
+            program,
+            cells,
+            bounds,
+        }
+    }
 
-    if is_v2 {
-        for noun in 0..100 {
-            for verb in 0..100 {
-                if find_first_value(&mut nums, &[noun, verb]) == 19690720 {
-                    return 100 * noun + verb;
+    fn output_for(&self, inputs: &[i64]) -> i64 {
+        let mut nums = self.program.to_vec();
+        for (cell, input) in self.cells.iter().zip(inputs.iter()) {
+            nums[*cell] = *input;
+        }
+        run_program(nums)
+    }
+
+    fn find(&self, target: i64) -> Option<Vec<i64>> {
+        match self.linear_coefficients() {
+            Some((base, coefficients)) => self
+                .solve_linear(target, base, &coefficients)
+                .filter(|inputs| self.output_for(inputs) == target)
+                .or_else(|| self.enumerate(target)),
+            None => self.enumerate(target),
+        }
+    }
+
+    fn enumerate(&self, target: i64) -> Option<Vec<i64>> {
+        let mut inputs: Vec<i64> = self.bounds.iter().map(|b| *b.start()).collect();
+
+        loop {
+            if self.output_for(&inputs) == target {
+                return Some(inputs);
+            }
+            if !self.advance(&mut inputs, self.cells.len()) {
+                return None;
+            }
+        }
+    }
+
+    // Steps the first `len` inputs like an odometer, last input fastest, returning false once exhausted
+    fn advance(&self, inputs: &mut [i64], len: usize) -> bool {
+        for i in (0..len).rev() {
+            if inputs[i] < *self.bounds[i].end() {
+                inputs[i] += 1;
+                return true;
+            }
+            inputs[i] = *self.bounds[i].start();
+        }
+
+        false
+    }
+
+    // Output at the lowest inputs plus the change from bumping each input by one, if probing a
+    // few other points agrees that output = base + sum(coefficient * (input - lowest input))
+    fn linear_coefficients(&self) -> Option<(i64, Vec<i64>)> {
+        let lows: Vec<i64> = self.bounds.iter().map(|b| *b.start()).collect();
+        let highs: Vec<i64> = self.bounds.iter().map(|b| *b.end()).collect();
+        let base = self.output_for(&lows);
+
+        let coefficients: Vec<i64> = (0..lows.len())
+            .map(|i| {
+                let mut inputs = lows.clone();
+                inputs[i] = (inputs[i] + 1).min(highs[i]);
+                self.output_for(&inputs) - base
+            })
+            .collect();
+
+        let predict = |inputs: &[i64]| -> i64 {
+            base + inputs
+                .iter()
+                .zip(lows.iter())
+                .zip(coefficients.iter())
+                .map(|((input, low), c)| (input - low) * c)
+                .sum::<i64>()
+        };
+        let middles: Vec<i64> = lows
+            .iter()
+            .zip(highs.iter())
+            .map(|(l, h)| (l + h) / 2)
+            .collect();
+        let mixed: Vec<i64> = (0..lows.len())
+            .map(|i| if i % 2 == 0 { highs[i] } else { lows[i] })
+            .collect();
+
+        if [highs.clone(), middles, mixed]
+            .iter()
+            .all(|probe| self.output_for(probe) == predict(probe))
+        {
+            Some((base, coefficients))
+        } else {
+            None
+        }
+    }
+
+    // Walks every input but the last in order and solves for the last, matching enumerate's order
+    fn solve_linear(&self, target: i64, base: i64, coefficients: &[i64]) -> Option<Vec<i64>> {
+        let last = self.cells.len() - 1;
+        let mut inputs: Vec<i64> = self.bounds.iter().map(|b| *b.start()).collect();
+
+        loop {
+            let partial: i64 = (0..last)
+                .map(|i| (inputs[i] - self.bounds[i].start()) * coefficients[i])
+                .sum();
+            let remaining = target - base - partial;
+
+            let last_offset = match coefficients[last] {
+                0 if remaining == 0 => Some(0),
+                0 => None,
+                c if remaining % c == 0 => Some(remaining / c),
+                _ => None,
+            };
+            if let Some(last_offset) = last_offset {
+                let last_input = self.bounds[last].start() + last_offset;
+                if self.bounds[last].contains(&last_input) {
+                    inputs[last] = last_input;
+                    return Some(inputs);
                 }
             }
+
+            if !self.advance(&mut inputs, last) {
+                return None;
+            }
         }
+    }
+}
+
+fn main() {
+    println!("{}", solve(true));
+}
 
-        0
+fn solve(is_v2: bool) -> i64 {
+    let line = read_line().unwrap();
+    let nums: Vec<i64> = line.split(",").map(|i| i.parse::<i64>().unwrap()).collect();
+    let search = InputSearch::new(&nums, vec![1, 2], vec![0..=99, 0..=99]);
+
+    if is_v2 {
+        let inputs = search.find(19690720).unwrap();
+        100 * inputs[0] + inputs[1]
     } else {
-        find_first_value(&mut nums, &[12, 2])
+        search.output_for(&[12, 2])
     }
 }
 
-fn find_first_value(nums: &mut Vec<usize>, replacements: &[usize; 2]) -> usize {
+fn run_program(mut nums: Vec<i64>) -> i64 {
     let mut index = 0;
-    let mut nums = nums.clone();
-    nums[1] = replacements[0];
-    nums[2] = replacements[1];
 
     loop {
         match nums[index] {
             1 | 2 => {
-                let val_1 = nums[nums[index + 1]];
-                let val_2 = nums[nums[index + 2]];
+                let val_1 = nums[nums[index + 1] as usize];
+                let val_2 = nums[nums[index + 2] as usize];
 
                 let value = match nums[index] {
                     1 => val_1 + val_2,
@@ -44,7 +167,7 @@ fn find_first_value(nums: &mut Vec<usize>, replacements: &[usize; 2]) -> usize {
                     _ => unreachable!(),
                 };
 
-                let replacement_index = nums[index + 3];
+                let replacement_index = nums[index + 3] as usize;
                 nums[replacement_index] = value
             }
             99 => break,
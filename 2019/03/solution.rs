@@ -1,10 +1,11 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
 use std::io;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 struct Point {
     x: i32,
     y: i32,
-    steps_to_point: i32,
 }
 
 impl Point {
@@ -17,165 +18,284 @@ impl Point {
     }
 }
 
-#[derive(Debug)]
-struct VLine {
-    top: i32,
-    bottom: i32,
-    hor_pos: i32,
-    start_point: Point,
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Orientation {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug, Clone)]
+struct Segment {
+    wire: usize,
+    orientation: Orientation,
+    start: Point,
+    steps_at_start: i32,
+    // Fixed coordinate (y for horizontal, x for vertical) and the covered range along the other axis
+    pos: i32,
+    low: i32,
+    high: i32,
+}
+
+impl Segment {
+    fn point_at(&self, along: i32) -> Point {
+        match self.orientation {
+            Orientation::Horizontal => Point {
+                x: along,
+                y: self.pos,
+            },
+            Orientation::Vertical => Point {
+                x: self.pos,
+                y: along,
+            },
+        }
+    }
+
+    fn steps_to(&self, point: &Point) -> i32 {
+        self.steps_at_start + (point.x - self.start.x).abs() + (point.y - self.start.y).abs()
+    }
 }
 
 #[derive(Debug)]
-struct HLine {
-    left: i32,
-    right: i32,
-    ver_pos: i32,
-    start_point: Point,
+struct Crossing {
+    point: Point,
+    wires: (usize, usize),
+    distance: i32,
+    delay: i32,
 }
 
-fn main() {
-    println!("{}", solve(true));
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum EventKind {
+    Insert,
+    Query,
+    Remove,
 }
 
-fn solve(is_v2: bool) -> i32 {
-    let (c1_v_lines, c1_h_lines) = process_line(&read_line().unwrap());
-    let (c2_v_lines, c2_h_lines) = process_line(&read_line().unwrap());
-    let mut intersections = find_intersections(&c1_v_lines[..], &c2_h_lines[..]);
-    let mut intersections2 = find_intersections(&c2_v_lines[..], &c1_h_lines[..]);
-    intersections.append(&mut intersections2);
+struct CrossingFinder {
+    segments: Vec<Segment>,
+    // (wire_a, wire_b, point) -> (fewest steps on wire_a, fewest steps on wire_b)
+    hits: BTreeMap<(usize, usize, Point), (i32, i32)>,
+}
+
+impl CrossingFinder {
+    fn new(wires: &[Vec<Segment>]) -> Self {
+        Self {
+            segments: wires.iter().flatten().cloned().collect(),
+            hits: BTreeMap::new(),
+        }
+    }
 
-    let filtered_points = intersections.into_iter().filter(|i| !i.is_origin());
+    fn find(mut self) -> Vec<Crossing> {
+        self.sweep_perpendicular();
+        self.sweep_collinear(Orientation::Horizontal);
+        self.sweep_collinear(Orientation::Vertical);
 
-    if is_v2 {
-        filtered_points.map(|i| i.steps_to_point).min().unwrap()
-    } else {
-        filtered_points.map(|i| i.manhattan_dist()).min().unwrap()
+        self.hits
+            .into_iter()
+            .map(|((wire_a, wire_b, point), (steps_a, steps_b))| Crossing {
+                point,
+                wires: (wire_a, wire_b),
+                distance: point.manhattan_dist(),
+                delay: steps_a + steps_b,
+            })
+            .collect()
     }
-}
 
-fn process_line(line: &str) -> (Vec<VLine>, Vec<HLine>) {
-    let mut curr_point = Point {
-        x: 0,
-        y: 0,
-        steps_to_point: 0,
-    };
-    let mut v_lines = vec![];
-    let mut h_lines = vec![];
-
-    line.split(",").for_each(|inst| {
-        let dir: &str = &inst[0..1];
-        let amt: i32 = (&inst[1..]).parse().unwrap();
-
-        match dir {
-            "U" => {
-                v_lines.push(VLine {
-                    top: curr_point.y + amt,
-                    bottom: curr_point.y,
-                    hor_pos: curr_point.x,
-                    start_point: curr_point,
-                });
-                curr_point = Point {
-                    x: curr_point.x,
-                    y: curr_point.y + amt,
-                    steps_to_point: curr_point.steps_to_point + amt,
-                };
-            }
-            "D" => {
-                v_lines.push(VLine {
-                    top: curr_point.y,
-                    bottom: curr_point.y - amt,
-                    hor_pos: curr_point.x,
-                    start_point: curr_point,
-                });
-                curr_point = Point {
-                    x: curr_point.x,
-                    y: curr_point.y - amt,
-                    steps_to_point: curr_point.steps_to_point + amt,
-                };
+    fn record(&mut self, a: usize, b: usize, point: Point) {
+        let (seg_a, seg_b) = (&self.segments[a], &self.segments[b]);
+        if seg_a.wire == seg_b.wire {
+            return;
+        }
+
+        let (first, second) = if seg_a.wire < seg_b.wire {
+            (seg_a, seg_b)
+        } else {
+            (seg_b, seg_a)
+        };
+        let steps = (first.steps_to(&point), second.steps_to(&point));
+
+        let entry = self
+            .hits
+            .entry((first.wire, second.wire, point))
+            .or_insert(steps);
+        entry.0 = entry.0.min(steps.0);
+        entry.1 = entry.1.min(steps.1);
+    }
+
+    fn sweep_perpendicular(&mut self) {
+        let mut events = vec![];
+        for (index, segment) in self.segments.iter().enumerate() {
+            match segment.orientation {
+                Orientation::Horizontal => {
+                    events.push((segment.low, EventKind::Insert, index));
+                    events.push((segment.high, EventKind::Remove, index));
+                }
+                Orientation::Vertical => events.push((segment.pos, EventKind::Query, index)),
             }
-            "R" => {
-                h_lines.push(HLine {
-                    left: curr_point.x,
-                    right: curr_point.x + amt,
-                    ver_pos: curr_point.y,
-                    start_point: curr_point,
-                });
-                curr_point = Point {
-                    x: curr_point.x + amt,
-                    y: curr_point.y,
-                    steps_to_point: curr_point.steps_to_point + amt,
-                };
+        }
+        events.sort();
+
+        let mut active: BTreeSet<(i32, usize)> = BTreeSet::new();
+        for (x, kind, index) in events {
+            match kind {
+                EventKind::Insert => {
+                    active.insert((self.segments[index].pos, index));
+                }
+                EventKind::Remove => {
+                    active.remove(&(self.segments[index].pos, index));
+                }
+                EventKind::Query => {
+                    let vertical = &self.segments[index];
+                    let found: Vec<(i32, usize)> = active
+                        .range((vertical.low, 0)..=(vertical.high, usize::MAX))
+                        .cloned()
+                        .collect();
+
+                    for (y, h_index) in found {
+                        self.record(index, h_index, Point { x, y });
+                    }
+                }
             }
-            "L" => {
-                h_lines.push(HLine {
-                    left: curr_point.x - amt,
-                    right: curr_point.x,
-                    ver_pos: curr_point.y,
-                    start_point: curr_point,
-                });
-                curr_point = Point {
-                    x: curr_point.x - amt,
-                    y: curr_point.y,
-                    steps_to_point: curr_point.steps_to_point + amt,
-                };
+        }
+    }
+
+    fn sweep_collinear(&mut self, orientation: Orientation) {
+        let mut lines: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
+        for (index, segment) in self.segments.iter().enumerate() {
+            if segment.orientation == orientation {
+                lines
+                    .entry(segment.pos)
+                    .or_insert_with(Vec::new)
+                    .push(index);
             }
-            _ => unreachable!(),
         }
-    });
 
-    v_lines.sort_by(|a, b| b.top.cmp(&a.top));
-    h_lines.sort_by(|a, b| b.ver_pos.cmp(&a.ver_pos));
+        for (_, mut indexes) in lines {
+            indexes.sort_by_key(|i| self.segments[*i].low);
+            let mut active: Vec<usize> = vec![];
+
+            for index in indexes {
+                let low = self.segments[index].low;
+                active.retain(|a| self.segments[*a].high >= low);
 
-    (v_lines, h_lines)
+                for other in active.clone() {
+                    let high = self.segments[index].high.min(self.segments[other].high);
+                    for along in low..=high {
+                        let point = self.segments[index].point_at(along);
+                        self.record(index, other, point);
+                    }
+                }
+
+                active.push(index);
+            }
+        }
+    }
 }
 
-fn find_intersections(v_lines: &[VLine], h_lines: &[HLine]) -> Vec<Point> {
-    let mut intersections = vec![];
+fn main() {
+    if env::args().any(|arg| arg == "--crossings") {
+        print_crossings();
+    } else {
+        println!("{}", solve(true));
+    }
+}
 
-    if v_lines.len() == 0 || h_lines.len() == 0 {
-        return intersections;
+fn solve(is_v2: bool) -> i32 {
+    let crossings = read_crossings();
+    let filtered_crossings = crossings.iter().filter(|c| !c.point.is_origin());
+
+    if is_v2 {
+        filtered_crossings.map(|c| c.delay).min().unwrap()
+    } else {
+        filtered_crossings.map(|c| c.distance).min().unwrap()
     }
+}
 
-    let mut v_lines_index: usize = 0;
-    let mut h_lines_index: usize = 0;
+fn print_crossings() {
+    for crossing in read_crossings() {
+        println!(
+            "wires {} & {} cross at ({}, {}): distance {}, delay {}",
+            crossing.wires.0,
+            crossing.wires.1,
+            crossing.point.x,
+            crossing.point.y,
+            crossing.distance,
+            crossing.delay
+        );
+    }
+}
 
-    while v_lines_index < v_lines.len() {
-        let v_line = &v_lines[v_lines_index.clone()];
+fn read_crossings() -> Vec<Crossing> {
+    let mut wires = vec![];
 
-        while h_lines_index < h_lines.len() && h_lines[h_lines_index.clone()].ver_pos > v_line.top {
-            h_lines_index += 1;
-        }
+    loop {
+        let line = read_line().unwrap();
 
-        if h_lines_index >= h_lines.len() {
-            v_lines_index += 1;
-            continue;
+        if line == "" {
+            break;
         }
 
-        let mut curr_h_index = h_lines_index.clone();
+        wires.push(process_line(wires.len(), &line));
+    }
 
-        while curr_h_index < h_lines.len() && h_lines[curr_h_index].ver_pos >= v_line.bottom {
-            let h_line = &h_lines[curr_h_index.clone()];
+    CrossingFinder::new(&wires).find()
+}
 
-            if h_line.left <= v_line.hor_pos && v_line.hor_pos <= h_line.right {
-                let v_line_dist = v_line.start_point.steps_to_point
-                    + (v_line.start_point.y - h_line.ver_pos).abs();
-                let h_line_dist = h_line.start_point.steps_to_point
-                    + (h_line.start_point.x - v_line.hor_pos).abs();
+fn process_line(wire: usize, line: &str) -> Vec<Segment> {
+    let mut curr_point = Point { x: 0, y: 0 };
+    let mut steps = 0;
 
-                intersections.push(Point {
-                    x: v_line.hor_pos,
-                    y: h_line.ver_pos,
-                    steps_to_point: v_line_dist + h_line_dist,
-                });
-            }
+    line.split(",")
+        .map(|inst| {
+            let dir: &str = &inst[0..1];
+            let amt: i32 = (&inst[1..]).parse().unwrap();
 
-            curr_h_index += 1;
-        }
+            let next_point = match dir {
+                "U" => Point {
+                    x: curr_point.x,
+                    y: curr_point.y + amt,
+                },
+                "D" => Point {
+                    x: curr_point.x,
+                    y: curr_point.y - amt,
+                },
+                "R" => Point {
+                    x: curr_point.x + amt,
+                    y: curr_point.y,
+                },
+                "L" => Point {
+                    x: curr_point.x - amt,
+                    y: curr_point.y,
+                },
+                _ => unreachable!(),
+            };
 
-        v_lines_index += 1;
-    }
+            let segment = if dir == "U" || dir == "D" {
+                Segment {
+                    wire,
+                    orientation: Orientation::Vertical,
+                    start: curr_point,
+                    steps_at_start: steps,
+                    pos: curr_point.x,
+                    low: curr_point.y.min(next_point.y),
+                    high: curr_point.y.max(next_point.y),
+                }
+            } else {
+                Segment {
+                    wire,
+                    orientation: Orientation::Horizontal,
+                    start: curr_point,
+                    steps_at_start: steps,
+                    pos: curr_point.y,
+                    low: curr_point.x.min(next_point.x),
+                    high: curr_point.x.max(next_point.x),
+                }
+            };
 
-    intersections
+            curr_point = next_point;
+            steps += amt;
+            segment
+        })
+        .collect()
 }
 
 fn read_line() -> Result<String, io::Error> {
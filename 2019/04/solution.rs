@@ -1,3 +1,4 @@
+use std::env;
 use std::io;
 
 #[derive(Debug)]
@@ -80,7 +81,8 @@ impl Iterator for PasswordIter {
     fn next(&mut self) -> Option<()> {
         let mut new_next = self.curr.build_next();
 
-        while !new_next.is_valid(self.is_v2) {
+        // Checked on every step, otherwise an invalid run past the max never ends
+        while !new_next.is_larger(&self.max) && !new_next.is_valid(self.is_v2) {
             new_next = new_next.build_next();
         }
 
@@ -93,12 +95,173 @@ impl Iterator for PasswordIter {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RunRule {
+    AtLeastPair,
+    ExactPair,
+}
+
+impl RunRule {
+    fn from_v2(is_v2: bool) -> Self {
+        if is_v2 {
+            RunRule::ExactPair
+        } else {
+            RunRule::AtLeastPair
+        }
+    }
+
+    fn run_matches(&self, run: usize) -> bool {
+        match self {
+            RunRule::AtLeastPair => run >= 2,
+            RunRule::ExactPair => run == 2,
+        }
+    }
+}
+
+struct PasswordCounter {
+    digits: usize,
+    rule: RunRule,
+    // Indexed by [position][previous digit][run length, capped at 3][rule already met]. A run
+    // length of 0 means only leading zeros so far, which are neither digits nor part of a run.
+    memo: Vec<Vec<Vec<Vec<Option<u64>>>>>,
+}
+
+impl PasswordCounter {
+    fn new(digits: usize, rule: RunRule) -> Self {
+        Self {
+            digits,
+            rule,
+            memo: vec![vec![vec![vec![None; 2]; 4]; 10]; digits + 1],
+        }
+    }
+
+    fn count_range(&mut self, min: u64, max: u64) -> u64 {
+        let below_min = if min == 0 {
+            0
+        } else {
+            self.count_up_to(min - 1)
+        };
+        self.count_up_to(max) - below_min
+    }
+
+    fn count_up_to(&mut self, limit: u64) -> u64 {
+        let largest = 10u64.pow(self.digits as u32) - 1;
+        let limit_digits: Vec<usize> =
+            format!("{:0width$}", limit.min(largest), width = self.digits)
+                .chars()
+                .map(|c| c.to_digit(10).unwrap() as usize)
+                .collect();
+
+        self.count_from(&limit_digits, 0, 0, 0, false, true)
+    }
+
+    fn count_from(
+        &mut self,
+        limit: &[usize],
+        pos: usize,
+        prev: usize,
+        run: usize,
+        met: bool,
+        tight: bool,
+    ) -> u64 {
+        if pos == self.digits {
+            return (run > 0 && (met || self.rule.run_matches(run))) as u64;
+        }
+
+        if !tight {
+            if let Some(count) = self.memo[pos][prev][run][met as usize] {
+                return count;
+            }
+        }
+
+        let max_digit = if tight { limit[pos] } else { 9 };
+        let mut count = 0;
+        for digit in prev..=max_digit {
+            let (next_run, next_met) = if run == 0 && digit == 0 {
+                (0, met)
+            } else if run > 0 && digit == prev {
+                ((run + 1).min(3), met)
+            } else {
+                (1, met || self.rule.run_matches(run))
+            };
+
+            count += self.count_from(
+                limit,
+                pos + 1,
+                digit,
+                next_run,
+                next_met,
+                tight && digit == max_digit,
+            );
+        }
+
+        if !tight {
+            self.memo[pos][prev][run][met as usize] = Some(count);
+        }
+        count
+    }
+}
+
 fn main() {
     println!("{}", solve(true));
 }
 
-fn solve(is_v2: bool) -> usize {
+fn solve(is_v2: bool) -> String {
     let line = read_line().unwrap();
+
+    if env::args().any(|arg| arg == "--check") {
+        let counted = count_passwords(&line, is_v2);
+        let brute_forced = brute_force_passwords(&line, is_v2);
+        // The reference iterator only understands six digit bounds
+        let iterated = if line.split("-").all(|bound| bound.len() == 6) {
+            iterate_passwords(&line, is_v2).to_string()
+        } else {
+            "n/a".to_string()
+        };
+        format!(
+            "counter: {}, iterator: {}, brute force: {}",
+            counted, iterated, brute_forced
+        )
+    } else {
+        count_passwords(&line, is_v2).to_string()
+    }
+}
+
+fn count_passwords(line: &str, is_v2: bool) -> u64 {
+    let mut bounds = line.split("-");
+    let min_str = bounds.next().unwrap();
+    let max_str = bounds.next().unwrap();
+
+    let mut counter = PasswordCounter::new(max_str.len(), RunRule::from_v2(is_v2));
+    counter.count_range(min_str.parse().unwrap(), max_str.parse().unwrap())
+}
+
+fn brute_force_passwords(line: &str, is_v2: bool) -> usize {
+    let mut bounds = line.split("-").map(|bound| bound.parse::<u64>().unwrap());
+    let (min, max) = (bounds.next().unwrap(), bounds.next().unwrap());
+    let rule = RunRule::from_v2(is_v2);
+
+    (min..=max)
+        .filter(|num| {
+            let digits = num.to_string().into_bytes();
+            if digits.windows(2).any(|pair| pair[0] > pair[1]) {
+                return false;
+            }
+
+            let mut runs = vec![1];
+            for pair in digits.windows(2) {
+                if pair[0] == pair[1] {
+                    *runs.last_mut().unwrap() += 1;
+                } else {
+                    runs.push(1);
+                }
+            }
+            runs.into_iter().any(|run| rule.run_matches(run))
+        })
+        .count()
+}
+
+fn iterate_passwords(line: &str, is_v2: bool) -> usize {
     let mut input_bounds = line
         .split("-")
         .map(|i| {
@@ -120,18 +283,17 @@ fn solve(is_v2: bool) -> usize {
     password_iter.count()
 }
 
+// Smallest never-decreasing password at or above the given one, minus one so the iterator's first
+// step lands on it. The first digit that drops below its predecessor takes that predecessor's value,
+// and so does every digit after it.
 fn set_new_min(password: &Password) -> Password {
-    let mut curr_max = password.val[0];
-    let mut new_min: Vec<u8> = password
-        .val
-        .iter()
-        .map(|i| {
-            if i > &curr_max {
-                curr_max = i.clone();
-            }
-            curr_max
-        })
-        .collect();
+    let mut new_min = password.val.clone();
+    if let Some(drop) = (1..new_min.len()).find(|i| new_min[*i] < new_min[i - 1]) {
+        let fill = new_min[drop - 1];
+        for digit in new_min[drop..].iter_mut() {
+            *digit = fill;
+        }
+    }
 
     new_min[5] -= 1;
     Password::from(new_min)
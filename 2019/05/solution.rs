@@ -1,25 +1,54 @@
+use std::fmt;
 use std::io;
 
-fn main() {
-    println!("{}", solve(true));
+struct Computer {
+    program: Vec<i64>,
+    pc: usize,
+    next_input: i64,
+    last_output: i64,
+    relative_offset: i64,
+    halted: bool,
 }
 
-fn solve(is_v2: bool) -> i32 {
-    let line = read_line().unwrap();
-    let mut nums: Vec<i32> = line.split(",").map(|i| i.parse::<i32>().unwrap()).collect();
+impl Computer {
+    fn new(program: Vec<i64>) -> Self {
+        Self {
+            program,
+            pc: 0,
+            next_input: 0,
+            last_output: 0,
+            relative_offset: 0,
+            halted: false,
+        }
+    }
 
-    let mut index: usize = 0;
-    let mut last_output = 0;
+    fn run_until_output(&mut self) -> i64 {
+        self.run_until_op(4);
+        self.last_output
+    }
 
-    loop {
-        let inst = nums[index];
+    fn run_until_op(&mut self, op_code: i64) {
+        while !self.halted {
+            let last_op = self.step();
+            if last_op == op_code {
+                break;
+            }
+        }
+    }
+
+    fn step(&mut self) -> i64 {
+        let inst = self.program[self.pc];
         let op = inst % 100;
-        let modes: [bool; 2] = [(inst / 100) % 2 == 1, (inst / 1000) % 2 == 1];
+        let modes: [u8; 3] = [
+            ((inst / 100) % 10) as u8,
+            ((inst / 1000) % 10) as u8,
+            ((inst / 10000) % 10) as u8,
+        ];
 
         match op {
             1 | 2 | 7 | 8 => {
-                let val_1 = get_val(&nums, modes[0], nums[index + 1]);
-                let val_2 = get_val(&nums, modes[1], nums[index + 2]);
+                let val_1 = self.get_val(&modes, 0);
+                let val_2 = self.get_val(&modes, 1);
 
                 let value = match op {
                     1 => val_1 + val_2,
@@ -31,42 +60,125 @@ fn solve(is_v2: bool) -> i32 {
                     _ => unreachable!(),
                 };
 
-                let addr = nums[index + 3] as usize;
-                nums[addr] = value;
-                index += 4;
+                let addr = self.get_addr(&modes, 2);
+                self.program[addr] = value;
+                self.pc += 4;
             }
             3 => {
-                let addr = nums[index + 1] as usize;
-                nums[addr] = if is_v2 { 5 } else { 1 };
-                index += 2;
+                let addr = self.get_addr(&modes, 0);
+                self.program[addr] = self.next_input;
+                self.pc += 2;
             }
             4 => {
-                let val_1 = get_val(&nums, modes[0], nums[index + 1]);
-                last_output = val_1;
-                index += 2
+                let val = self.get_val(&modes, 0);
+                self.last_output = val;
+                self.pc += 2;
             }
             5 | 6 => {
-                let val_1 = get_val(&nums, modes[0], nums[index + 1]);
-                let val_2 = get_val(&nums, modes[1], nums[index + 2]);
+                let val_1 = self.get_val(&modes, 0);
+                let val_2 = self.get_val(&modes, 1);
 
                 if op == 5 && val_1 != 0 || op == 6 && val_1 == 0 {
-                    index = val_2 as usize;
+                    self.pc = val_2 as usize;
                 } else {
-                    index += 3;
+                    self.pc += 3;
                 }
             }
-            99 => break,
+            9 => {
+                let val = self.get_val(&modes, 0);
+                self.relative_offset += val;
+                self.pc += 2;
+            }
+            99 => self.halted = true,
             _ => panic!("Unknown op: {}", op),
         }
+
+        op
+    }
+
+    fn get_val(&mut self, modes: &[u8; 3], instr_index: usize) -> i64 {
+        let addr = self.get_addr(modes, instr_index);
+        self.program[addr]
+    }
+
+    fn get_addr(&mut self, modes: &[u8; 3], instr_index: usize) -> usize {
+        let pc_index = self.pc + 1 + instr_index;
+        let addr = match modes[instr_index] {
+            0 => self.program[pc_index] as usize,
+            1 => pc_index,
+            2 => (self.program[pc_index] + self.relative_offset) as usize,
+            _ => panic!("Unknown address mode: {}", modes[instr_index]),
+        };
+
+        self.expand_program(addr);
+        addr
     }
-    last_output
+
+    fn expand_program(&mut self, addr: usize) {
+        if addr >= self.program.len() {
+            self.program.resize(addr + 1, 0);
+        }
+    }
+}
+
+#[derive(Debug)]
+enum DiagnosticError {
+    NoOutput,
+    FailedTest { test: usize, output: i64 },
 }
 
-fn get_val(nums: &Vec<i32>, immediate_mode: bool, val: i32) -> i32 {
-    if immediate_mode {
-        val
-    } else {
-        nums[val as usize]
+impl fmt::Display for DiagnosticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiagnosticError::NoOutput => write!(f, "Diagnostic program produced no output"),
+            DiagnosticError::FailedTest { test, output } => {
+                write!(f, "Diagnostic test {} failed with output {}", test, output)
+            }
+        }
+    }
+}
+
+fn main() {
+    match solve(true) {
+        Ok(code) => println!("{}", code),
+        Err(error) => println!("{}", error),
+    }
+}
+
+fn solve(is_v2: bool) -> Result<i64, DiagnosticError> {
+    let line = read_line().unwrap();
+    let nums: Vec<i64> = line.split(",").map(|i| i.parse::<i64>().unwrap()).collect();
+    let system_id = if is_v2 { 5 } else { 1 };
+
+    check_diagnostics(&run_diagnostics(nums, system_id))
+}
+
+fn run_diagnostics(nums: Vec<i64>, system_id: i64) -> Vec<i64> {
+    let mut c = Computer::new(nums);
+    c.next_input = system_id;
+    let mut outputs = vec![];
+
+    loop {
+        let output = c.run_until_output();
+        if c.halted {
+            break;
+        }
+        outputs.push(output);
+    }
+
+    outputs
+}
+
+// Every output before the diagnostic code is a test result, where anything but 0 is a failure
+fn check_diagnostics(outputs: &[i64]) -> Result<i64, DiagnosticError> {
+    let (code, tests) = outputs.split_last().ok_or(DiagnosticError::NoOutput)?;
+
+    match tests.iter().position(|output| *output != 0) {
+        Some(test) => Err(DiagnosticError::FailedTest {
+            test: test + 1,
+            output: tests[test],
+        }),
+        None => Ok(*code),
     }
 }
 
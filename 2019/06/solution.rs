@@ -1,104 +1,255 @@
 use std::collections::HashMap;
+use std::env;
+use std::fmt;
 use std::io;
 
 #[derive(Debug)]
-struct Node {
-    depth: usize,
-    children: Vec<String>,
-    parent: Option<String>,
+enum OrbitError {
+    MalformedLine(String),
+    MultipleParents(String),
+    MissingRoot(String),
+    Orphan(String),
+    Cycle(Vec<String>),
+    UnknownBody(String),
+    NoTransfer(String, String),
 }
 
-impl Node {
-    fn new() -> Self {
-        Node {
-            depth: 0,
-            children: vec![],
-            parent: None,
+impl fmt::Display for OrbitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OrbitError::MalformedLine(line) => write!(f, "Malformed orbit line: {:?}", line),
+            OrbitError::MultipleParents(body) => write!(f, "{} orbits more than one body", body),
+            OrbitError::MissingRoot(root) => write!(f, "Root body {} is not in the map", root),
+            OrbitError::Orphan(body) => write!(f, "{} does not orbit anything", body),
+            OrbitError::Cycle(bodies) => write!(f, "Orbit cycle: {}", bodies.join(" -> ")),
+            OrbitError::UnknownBody(body) => write!(f, "Unknown body: {}", body),
+            OrbitError::NoTransfer(a, b) => {
+                write!(f, "One of {} and {} directly orbits the other", a, b)
+            }
         }
     }
 }
 
-fn main() {
-    println!("{}", solve(true));
+#[derive(Debug)]
+struct OrbitTree {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    parents: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    depths: Vec<usize>,
+    root: usize,
 }
 
-fn solve(is_v2: bool) -> usize {
-    let mut nodes: HashMap<String, Node> = HashMap::new();
+impl OrbitTree {
+    fn parse(lines: &[String], root_name: &str) -> Result<Self, OrbitError> {
+        let mut tree = Self {
+            names: vec![],
+            ids: HashMap::new(),
+            parents: vec![],
+            children: vec![],
+            depths: vec![],
+            root: 0,
+        };
 
-    loop {
-        let line = read_line().unwrap();
+        for line in lines {
+            let mut parts = line.split(")");
+            let (from_name, to_name) = match (parts.next(), parts.next(), parts.next()) {
+                (Some(from), Some(to), None) if from != "" && to != "" => (from, to),
+                _ => return Err(OrbitError::MalformedLine(line.to_string())),
+            };
 
-        if line == "" {
-            break;
+            let from = tree.intern(from_name);
+            let to = tree.intern(to_name);
+
+            if tree.parents[to].is_some() {
+                return Err(OrbitError::MultipleParents(to_name.to_string()));
+            }
+            tree.parents[to] = Some(from);
+            tree.children[from].push(to);
         }
 
-        let mut parts = line.split(")");
-        let from_node_name = parts.next().unwrap().to_string();
-        let to_node_name = parts.next().unwrap().to_string();
+        tree.root = match tree.ids.get(root_name) {
+            Some(root) => *root,
+            None => return Err(OrbitError::MissingRoot(root_name.to_string())),
+        };
 
-        if !nodes.contains_key(&from_node_name) {
-            nodes.insert(from_node_name.clone(), Node::new());
+        let orphan =
+            (0..tree.names.len()).find(|id| *id != tree.root && tree.parents[*id].is_none());
+        if let Some(orphan) = orphan {
+            return Err(OrbitError::Orphan(tree.names[orphan].clone()));
         }
-        if !nodes.contains_key(&to_node_name) {
-            nodes.insert(to_node_name.clone(), Node::new());
+
+        if let Some(parent) = tree.parents[tree.root] {
+            return Err(tree.find_cycle(parent));
         }
 
-        let from_node = nodes.get_mut(&from_node_name).unwrap();
-        from_node.children.push(to_node_name.clone());
+        let reached = tree.build_depths();
+        if let Some(unreached) = (0..tree.names.len()).find(|id| !reached[*id]) {
+            return Err(tree.find_cycle(unreached));
+        }
 
-        let mut to_node = nodes.get_mut(&to_node_name).unwrap();
-        to_node.parent = Some(from_node_name)
+        Ok(tree)
     }
 
-    build_depths(&mut nodes, "COM", 0);
+    fn intern(&mut self, name: &str) -> usize {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
 
-    if is_v2 {
-        find_jumps_between(&nodes, "YOU", "SAN")
-    } else {
-        nodes.values().map(|node| node.depth).sum()
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        self.parents.push(None);
+        self.children.push(vec![]);
+        self.depths.push(0);
+        id
+    }
+
+    fn build_depths(&mut self) -> Vec<bool> {
+        let mut reached = vec![false; self.names.len()];
+        let mut stack = vec![self.root];
+        reached[self.root] = true;
+
+        while let Some(id) = stack.pop() {
+            for child in self.children[id].clone() {
+                self.depths[child] = self.depths[id] + 1;
+                reached[child] = true;
+                stack.push(child);
+            }
+        }
+
+        reached
+    }
+
+    fn find_cycle(&self, start: usize) -> OrbitError {
+        let mut visited_at = HashMap::new();
+        let mut path = vec![];
+        let mut curr = start;
+
+        while !visited_at.contains_key(&curr) {
+            visited_at.insert(curr, path.len());
+            path.push(curr);
+            curr = match self.parents[curr] {
+                Some(parent) => parent,
+                None => return OrbitError::Orphan(self.names[curr].clone()),
+            };
+        }
+
+        let mut cycle: Vec<String> = path[visited_at[&curr]..]
+            .iter()
+            .map(|id| self.names[*id].clone())
+            .collect();
+        cycle.push(self.names[curr].clone());
+        OrbitError::Cycle(cycle)
+    }
+
+    fn id(&self, name: &str) -> Result<usize, OrbitError> {
+        self.ids
+            .get(name)
+            .cloned()
+            .ok_or_else(|| OrbitError::UnknownBody(name.to_string()))
+    }
+
+    fn total_orbits(&self) -> usize {
+        self.depths.iter().sum()
+    }
+
+    fn lowest_common_ancestor(&self, a: &str, b: &str) -> Result<&str, OrbitError> {
+        let (mut a, mut b) = (self.id(a)?, self.id(b)?);
+
+        while self.depths[a] > self.depths[b] {
+            a = self.parents[a].unwrap();
+        }
+        while self.depths[b] > self.depths[a] {
+            b = self.parents[b].unwrap();
+        }
+        while a != b {
+            a = self.parents[a].unwrap();
+            b = self.parents[b].unwrap();
+        }
+
+        Ok(&self.names[a])
+    }
+
+    fn distance(&self, a: &str, b: &str) -> Result<usize, OrbitError> {
+        let ancestor = self.id(self.lowest_common_ancestor(a, b)?)?;
+        Ok(self.depths[self.id(a)?] + self.depths[self.id(b)?] - 2 * self.depths[ancestor])
+    }
+
+    fn subtree(&self, name: &str) -> Result<Vec<&str>, OrbitError> {
+        let mut bodies = vec![];
+        let mut stack = vec![self.id(name)?];
+
+        while let Some(id) = stack.pop() {
+            bodies.push(self.names[id].as_str());
+            stack.extend(self.children[id].iter().rev());
+        }
+
+        Ok(bodies)
+    }
+
+    fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph orbits {\n");
+        for (id, parent) in self.parents.iter().enumerate() {
+            if let Some(parent) = parent {
+                dot.push_str(&format!(
+                    "    \"{}\" -> \"{}\";\n",
+                    self.names[*parent], self.names[id]
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        dot
     }
 }
 
-fn build_depths(nodes: &mut HashMap<String, Node>, node_name: &str, depth: usize) {
-    {
-        let mut node = nodes.get_mut(node_name).unwrap();
-        node.depth = depth;
+fn main() {
+    let tree = match OrbitTree::parse(&read_lines(), "COM") {
+        Ok(tree) => tree,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.iter().map(|arg| arg.as_str()).collect::<Vec<&str>>()[..] {
+        ["--dot"] => Ok(tree.to_dot()),
+        ["--subtree", body] => tree.subtree(body).map(|bodies| bodies.join("\n")),
+        _ => solve(&tree, true).map(|answer| answer.to_string()),
+    };
+
+    match result {
+        Ok(output) => println!("{}", output.trim_end()),
+        Err(error) => eprintln!("{}", error),
     }
+}
 
-    nodes[node_name]
-        .children
-        .clone()
-        .iter()
-        .for_each(|child_name| build_depths(nodes, child_name, depth + 1));
+fn solve(tree: &OrbitTree, is_v2: bool) -> Result<usize, OrbitError> {
+    if is_v2 {
+        // Jumps are between the bodies YOU and SAN orbit, not YOU and SAN themselves
+        tree.distance("YOU", "SAN")?
+            .checked_sub(2)
+            .ok_or_else(|| OrbitError::NoTransfer("YOU".to_string(), "SAN".to_string()))
+    } else {
+        Ok(tree.total_orbits())
+    }
 }
 
-fn find_jumps_between(
-    nodes: &HashMap<String, Node>,
-    node_1_target: &str,
-    node_2_target: &str,
-) -> usize {
-    let mut node_1_target = node_1_target.to_string();
-    let mut node_2_target = node_2_target.to_string();
-    let mut jumps = 0;
-
-    while nodes[&node_1_target].parent != nodes[&node_2_target].parent {
-        let node_1_parent = nodes[&node_1_target].parent.as_ref();
-        let node_2_parent = nodes[&node_2_target].parent.as_ref();
-
-        if nodes[&node_2_target].depth < nodes[&node_1_target].depth {
-            node_1_target = node_1_parent.unwrap().to_string();
-            jumps += 1;
-        } else if nodes[&node_2_target].depth > nodes[&node_1_target].depth {
-            node_2_target = node_2_parent.unwrap().to_string();
-            jumps += 1;
-        } else {
-            node_1_target = node_1_parent.unwrap().to_string();
-            node_2_target = node_2_parent.unwrap().to_string();
-            jumps += 2;
+fn read_lines() -> Vec<String> {
+    let mut lines = vec![];
+
+    loop {
+        let line = read_line().unwrap();
+
+        if line == "" {
+            break;
         }
+
+        lines.push(line);
     }
 
-    jumps
+    lines
 }
 
 fn read_line() -> Result<String, io::Error> {
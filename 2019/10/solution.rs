@@ -1,135 +1,137 @@
 use std::cmp::Ordering;
-use std::collections::HashMap;
-use std::f64;
+use std::collections::{BTreeMap, VecDeque};
+use std::env;
 use std::io;
 
 #[derive(Eq, PartialEq, Clone, Debug)]
 struct Asteroid {
-    x: u32,
-    y: u32,
+    x: i64,
+    y: i64,
 }
 
 impl Asteroid {
-    fn dist_to(&self, other: &Self) -> f64 {
-        ((f64::from(self.x) - f64::from(other.x)).powi(2)
-            + (f64::from(self.y) - f64::from(other.y)).powi(2))
-        .sqrt()
-    }
-}
-
-#[derive(Hash, Eq, PartialEq, Clone, Debug)]
-struct Angle {
-    numerator: i64,
-    denominator: i64,
-    first_180: bool,
-}
-
-impl Angle {
-    fn create_from_asteroids(source: &Asteroid, dest: &Asteroid) -> Self {
-        let numerator = i64::from(source.x) - i64::from(dest.x);
-        let denominator = i64::from(source.y) - i64::from(dest.y);
-        let first_180 = source.x < dest.x || (source.x == dest.x && dest.y < source.y);
-
-        let mut angle = Self {
-            numerator,
-            denominator,
-            first_180,
-        };
-        angle.simplify();
-        angle
-    }
-
-    fn to_rads(&self) -> f64 {
-        if self.denominator == 0 {
-            return if self.first_180 {
-                f64::consts::FRAC_PI_2
-            } else {
-                f64::consts::FRAC_PI_2 * 3f64
-            };
-        }
+    fn dist_sq_to(&self, other: &Self) -> i64 {
+        (self.x - other.x).pow(2) + (self.y - other.y).pow(2)
+    }
+}
 
-        let rads = if self.numerator <= 0 {
-            ((self.numerator.abs() as f64) / (self.denominator.abs() as f64)).atan()
-        } else {
-            ((self.denominator.abs() as f64) / (self.numerator.abs() as f64)).atan()
-                + f64::consts::FRAC_PI_2
-        };
+// Reduced step from a source asteroid, with y growing downwards as in the input
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+struct Direction {
+    dx: i64,
+    dy: i64,
+}
 
-        if self.first_180 {
-            rads
-        } else {
-            rads + f64::consts::PI
+impl Direction {
+    fn between(source: &Asteroid, dest: &Asteroid) -> Self {
+        let dx = dest.x - source.x;
+        let dy = dest.y - source.y;
+        let common_factor = gcd(dx.abs(), dy.abs());
+
+        Self {
+            dx: dx / common_factor,
+            dy: dy / common_factor,
         }
     }
 
-    fn simplify(&mut self) {
-        let common_factor = gcd(self.numerator, self.denominator);
-        self.numerator /= common_factor;
-        self.denominator /= common_factor;
-
-        if self.denominator < 0 {
-            self.numerator *= -1;
-            self.denominator *= -1;
+    // 0 for straight up and the right hand side, 1 for straight down and the left hand side
+    fn half(&self) -> u8 {
+        if self.dx > 0 || (self.dx == 0 && self.dy < 0) {
+            0
+        } else {
+            1
         }
     }
+
+    fn cross(&self, other: &Self) -> i64 {
+        self.dx * other.dy - self.dy * other.dx
+    }
 }
 
-impl Ord for Angle {
+// Clockwise from straight up
+impl Ord for Direction {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.to_rads().partial_cmp(&other.to_rads()).unwrap()
+        self.half()
+            .cmp(&other.half())
+            .then_with(|| 0.cmp(&self.cross(other)))
     }
 }
 
-impl PartialOrd for Angle {
+impl PartialOrd for Direction {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
 type Asteroids = Vec<Asteroid>;
-type RelAsteroidMap = HashMap<Angle, Asteroids>;
+type RelAsteroidMap = BTreeMap<Direction, Asteroids>;
+
+struct VaporisationOrder {
+    // One queue per direction, clockwise from straight up, each nearest first
+    queues: Vec<VecDeque<Asteroid>>,
+    index: usize,
+}
+
+impl VaporisationOrder {
+    fn new(relative_asteroid_map: RelAsteroidMap) -> Self {
+        Self {
+            queues: relative_asteroid_map
+                .into_iter()
+                .map(|(_, asteroids)| asteroids.into_iter().collect())
+                .collect(),
+            index: 0,
+        }
+    }
+}
+
+impl Iterator for VaporisationOrder {
+    type Item = Asteroid;
+
+    fn next(&mut self) -> Option<Asteroid> {
+        for _ in 0..self.queues.len() {
+            let queue_index = self.index % self.queues.len();
+            self.index += 1;
+
+            if let Some(asteroid) = self.queues[queue_index].pop_front() {
+                return Some(asteroid);
+            }
+        }
+
+        None
+    }
+}
 
 fn main() {
     println!("{}", solve(true));
 }
 
-fn solve(is_v2: bool) -> u32 {
-    let asteroids = build_asteroids();
+fn solve(is_v2: bool) -> String {
+    let (asteroids, width, height) = build_asteroids();
+
+    if env::args().any(|arg| arg == "--heatmap") {
+        return build_heatmap(&asteroids, width, height);
+    }
 
     // highest visibility asteroid's relative map
-    let mut relative_asteroid_map = asteroids
+    let relative_asteroid_map = asteroids
         .iter()
         .map(|a| build_relative_asteroid_map(&asteroids, &a))
         .max_by(|a, b| a.len().cmp(&b.len()))
         .unwrap();
 
     if is_v2 {
-        let mut destroyed_count = 0;
-        let mut keys = relative_asteroid_map
-            .keys()
-            .map(|k| k.clone())
-            .collect::<Vec<Angle>>();
-        keys.sort();
-
-        loop {
-            for angle in &keys {
-                let v = relative_asteroid_map.get_mut(&angle).unwrap();
-                if v.len() != 0 {
-                    let popped_asteroid = v.pop().unwrap();
-                    destroyed_count += 1;
-                    if destroyed_count == 200 {
-                        return popped_asteroid.x * 100 + popped_asteroid.y;
-                    }
-                }
-            }
-        }
+        let asteroid = VaporisationOrder::new(relative_asteroid_map)
+            .nth(199)
+            .unwrap();
+        (asteroid.x * 100 + asteroid.y).to_string()
     } else {
-        relative_asteroid_map.len() as u32
+        relative_asteroid_map.len().to_string()
     }
 }
 
-fn build_asteroids() -> Asteroids {
+fn build_asteroids() -> (Asteroids, usize, usize) {
     let mut y = 0;
+    let mut width = 0;
     let mut asteroids: Asteroids = vec![];
 
     loop {
@@ -138,53 +140,60 @@ fn build_asteroids() -> Asteroids {
             break;
         }
 
+        width = width.max(line.len());
         asteroids.append(
             &mut line
                 .chars()
                 .enumerate()
                 .filter(|(_, c)| *c == '#')
-                .map(|(x, _)| x as u32)
-                .map(|x| Asteroid { x, y })
+                .map(|(x, _)| Asteroid { x: x as i64, y })
                 .collect::<Asteroids>(),
         );
 
         y += 1;
     }
 
-    asteroids
+    (asteroids, width, y as usize)
 }
 
-// map of asteroids, grouped by angle from source asteroid
-// grouped values are sorted in descending distance from source
+// map of asteroids, grouped by direction from source asteroid
+// grouped values are sorted in ascending distance from source
 fn build_relative_asteroid_map(asteroids: &Asteroids, source: &Asteroid) -> RelAsteroidMap {
-    let mut asteroid_map: RelAsteroidMap =
-        asteroids.iter().fold(HashMap::new(), |mut map, asteroid| {
-            if asteroid == source {
-                return map;
-            }
+    let mut asteroid_map: RelAsteroidMap = BTreeMap::new();
+
+    for asteroid in asteroids {
+        if asteroid != source {
+            asteroid_map
+                .entry(Direction::between(source, asteroid))
+                .or_insert_with(Vec::new)
+                .push(asteroid.clone());
+        }
+    }
 
-            let angle = Angle::create_from_asteroids(source, asteroid);
-            if !map.contains_key(&angle) {
-                map.insert(angle.clone(), vec![]);
-            }
-            map.get_mut(&angle).unwrap().push(asteroid.clone());
+    for group in asteroid_map.values_mut() {
+        group.sort_by_key(|a| source.dist_sq_to(a));
+    }
 
-            map
-        });
+    asteroid_map
+}
 
-    let keys = asteroid_map
-        .keys()
-        .map(|k| k.clone())
-        .collect::<Vec<Angle>>();
+// Plain PGM image where each asteroid's brightness is the number of asteroids it can see
+fn build_heatmap(asteroids: &Asteroids, width: usize, height: usize) -> String {
+    let mut counts = vec![vec![0; width]; height];
+    for asteroid in asteroids {
+        counts[asteroid.y as usize][asteroid.x as usize] =
+            build_relative_asteroid_map(asteroids, asteroid).len();
+    }
+    let max_count = counts.iter().flatten().max().cloned().unwrap_or(0).max(1);
 
-    for angle in keys {
-        asteroid_map
-            .get_mut(&angle)
-            .unwrap()
-            .sort_by(|a, b| source.dist_to(b).partial_cmp(&source.dist_to(a)).unwrap())
+    let mut output = format!("P2\n{} {}\n{}\n", width, height, max_count);
+    for row in counts {
+        let cells: Vec<String> = row.iter().map(|count| count.to_string()).collect();
+        output.push_str(&cells.join(" "));
+        output.push('\n');
     }
 
-    asteroid_map
+    output.trim_end().to_string()
 }
 
 fn gcd(a: i64, b: i64) -> i64 {
@@ -1,140 +1,146 @@
+use std::env;
 use std::io;
+use std::thread;
 
-#[derive(PartialEq, Clone, Copy)]
-struct Dim {
-    pos: i32,
-    vel: i32,
+#[derive(PartialEq, Clone, Copy, Debug)]
+struct Body<const N: usize> {
+    pos: [i64; N],
+    vel: [i64; N],
 }
 
-impl Dim {
-    fn new(pos: i32) -> Self {
-        Self { pos, vel: 0 }
+impl<const N: usize> Body<N> {
+    fn new(pos: [i64; N]) -> Self {
+        Self { pos, vel: [0; N] }
     }
 
-    fn adjust_velocities(dim1: &mut Self, dim2: &mut Self) {
-        let diff = dim1.pos - dim2.pos;
-
-        if diff > 0 {
-            dim1.vel -= 1;
-            dim2.vel += 1;
-        } else if diff < 0 {
-            dim1.vel += 1;
-            dim2.vel -= 1;
+    fn step(&mut self) {
+        for axis in 0..N {
+            self.pos[axis] += self.vel[axis];
         }
     }
 
-    fn step(&mut self) {
-        self.pos += self.vel
+    fn energy(&self) -> i64 {
+        let pos_sum: i64 = self.pos.iter().map(|p| p.abs()).sum();
+        let vel_sum: i64 = self.vel.iter().map(|v| v.abs()).sum();
+        pos_sum * vel_sum
     }
 }
 
-struct Moon {
-    x: Dim,
-    y: Dim,
-    z: Dim,
+struct Simulation<const N: usize> {
+    bodies: Vec<Body<N>>,
+    step_count: u64,
+    energy_history: Option<Vec<i64>>,
 }
 
-impl Moon {
-    fn adjust_velocities(moon1: &mut Moon, moon2: &mut Moon) {
-        Dim::adjust_velocities(&mut moon1.x, &mut moon2.x);
-        Dim::adjust_velocities(&mut moon1.y, &mut moon2.y);
-        Dim::adjust_velocities(&mut moon1.z, &mut moon2.z);
-    }
-
-    fn new(x: i32, y: i32, z: i32) -> Self {
+impl<const N: usize> Simulation<N> {
+    fn new(bodies: Vec<Body<N>>) -> Self {
         Self {
-            x: Dim::new(x),
-            y: Dim::new(y),
-            z: Dim::new(z),
+            bodies,
+            step_count: 0,
+            energy_history: None,
         }
     }
 
-    fn get_dim(&self, index: usize) -> &Dim {
-        match index {
-            0 => &self.x,
-            1 => &self.y,
-            2 => &self.z,
-            _ => panic!("unknown dim index"),
-        }
+    fn record_energy(&mut self) {
+        self.energy_history = Some(vec![self.energy()]);
     }
 
-    fn step(&mut self) {
-        self.x.step();
-        self.y.step();
-        self.z.step();
+    fn energy(&self) -> i64 {
+        self.bodies.iter().map(Body::energy).sum()
     }
 
-    fn energy(&self) -> i32 {
-        let pos_sum = self.x.pos.abs() + self.y.pos.abs() + self.z.pos.abs();
-        let vel_sum = self.x.vel.abs() + self.y.vel.abs() + self.z.vel.abs();
-        pos_sum * vel_sum
-    }
-}
+    fn step(&mut self) {
+        for axis in 0..N {
+            for i in 0..self.bodies.len() {
+                for j in (i + 1)..self.bodies.len() {
+                    let pull = gravity(self.bodies[i].pos[axis], self.bodies[j].pos[axis]);
+                    self.bodies[i].vel[axis] += pull;
+                    self.bodies[j].vel[axis] -= pull;
+                }
+            }
+        }
 
-struct Moons {
-    moons: Vec<Moon>,
-    initial: [Vec<Dim>; 3],
-    repeat_step: [Option<u32>; 3],
-    step_count: u32,
-}
+        self.bodies.iter_mut().for_each(Body::step);
+        self.step_count += 1;
 
-impl Moons {
-    fn new(moons: Vec<Moon>) -> Self {
-        let initial = [
-            moons.iter().map(|m| m.x).collect::<Vec<Dim>>(),
-            moons.iter().map(|m| m.y).collect::<Vec<Dim>>(),
-            moons.iter().map(|m| m.z).collect::<Vec<Dim>>(),
-        ];
-        let repeat_step = [None, None, None];
-        Self {
-            moons,
-            step_count: 0,
-            initial,
-            repeat_step,
+        let energy = self.energy();
+        if let Some(history) = self.energy_history.as_mut() {
+            history.push(energy);
         }
     }
 
-    fn energy(&self) -> i32 {
-        self.moons.iter().map(Moon::energy).sum()
-    }
-
-    fn all_repeats_found(&self) -> bool {
-        self.repeat_step.iter().all(|r| r.is_some())
+    fn axis(&self, axis: usize) -> Vec<i64> {
+        self.bodies.iter().map(|b| b.pos[axis]).collect()
     }
 
-    fn step(&mut self) {
-        self.calculate_velocities();
-        self.step_positions();
-        self.step_count += 1;
-
-        (0..3).for_each(|dim_index| self.check_set_repeat_step(dim_index));
+    // Axes never influence each other, so the whole system repeats at the LCM of each axis' period
+    fn period(&self) -> u64 {
+        let handles: Vec<thread::JoinHandle<u64>> = (0..N)
+            .map(|axis| {
+                let positions = self.axis(axis);
+                thread::spawn(move || axis_period(positions))
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .fold(1, lcm)
     }
 
-    fn check_set_repeat_step(&mut self, index: usize) {
-        if self.repeat_step[index].is_none() {
-            let curr_dims = self.moons.iter().map(|m| m.get_dim(index));
-            let initial_dims = &self.initial[index];
+    fn trajectory_csv(&mut self, axis_names: &[String], steps: u64) -> String {
+        let mut header = vec!["step".to_string(), "body".to_string()];
+        header.extend(axis_names.iter().cloned());
+        header.extend(axis_names.iter().map(|name| format!("v{}", name)));
+
+        let mut rows = vec![header.join(",")];
+        for _ in 0..=steps {
+            for (index, body) in self.bodies.iter().enumerate() {
+                let mut row = vec![self.step_count.to_string(), index.to_string()];
+                row.extend(
+                    body.pos
+                        .iter()
+                        .chain(body.vel.iter())
+                        .map(|v| v.to_string()),
+                );
+                rows.push(row.join(","));
+            }
 
-            if curr_dims.zip(initial_dims).all(|(curr, init)| curr == init) {
-                self.repeat_step[index] = Some(self.step_count);
+            if self.step_count < steps {
+                self.step();
             }
         }
+
+        rows.join("\n")
     }
+}
 
-    fn calculate_velocities(&mut self) {
-        let len = self.moons.len();
+fn gravity(pos: i64, other_pos: i64) -> i64 {
+    (other_pos - pos).signum()
+}
 
-        for i in 1..len {
-            let (head, tail) = self.moons.split_at_mut(i);
-            let mut moon1 = head.last_mut().unwrap();
-            for mut moon2 in tail {
-                Moon::adjust_velocities(&mut moon1, &mut moon2);
+// Steps until a single axis returns to its starting positions with every body at rest
+fn axis_period(initial: Vec<i64>) -> u64 {
+    let mut pos = initial.clone();
+    let mut vel = vec![0; initial.len()];
+    let mut steps = 0;
+
+    loop {
+        for i in 0..pos.len() {
+            for j in (i + 1)..pos.len() {
+                let pull = gravity(pos[i], pos[j]);
+                vel[i] += pull;
+                vel[j] -= pull;
             }
         }
-    }
+        for (p, v) in pos.iter_mut().zip(vel.iter()) {
+            *p += *v;
+        }
+        steps += 1;
 
-    fn step_positions(&mut self) {
-        self.moons.iter_mut().for_each(Moon::step);
+        if pos == initial && vel.iter().all(|v| *v == 0) {
+            return steps;
+        }
     }
 }
 
@@ -142,8 +148,47 @@ fn main() {
     println!("{}", solve(true));
 }
 
-fn solve(is_v2: bool) -> u64 {
-    let mut moons: Vec<Moon> = vec![];
+fn solve(is_v2: bool) -> String {
+    let (axis_names, coords) = read_coords();
+
+    // The axis count is only known once the input has been read, so pick the matching engine
+    match axis_names.len() {
+        1 => run(&axis_names, bodies_from::<1>(&coords), is_v2),
+        2 => run(&axis_names, bodies_from::<2>(&coords), is_v2),
+        3 => run(&axis_names, bodies_from::<3>(&coords), is_v2),
+        4 => run(&axis_names, bodies_from::<4>(&coords), is_v2),
+        axes => format!("Unsupported axis count: {}", axes),
+    }
+}
+
+fn run<const N: usize>(axis_names: &[String], bodies: Vec<Body<N>>, is_v2: bool) -> String {
+    let mut simulation = Simulation::new(bodies);
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.iter().map(|arg| arg.as_str()).collect::<Vec<&str>>()[..] {
+        ["--csv", steps] => simulation.trajectory_csv(axis_names, steps.parse().unwrap()),
+        ["--energy", steps] => {
+            simulation.record_energy();
+            for _ in 0..steps.parse::<u64>().unwrap() {
+                simulation.step();
+            }
+            let history = simulation.energy_history.unwrap();
+            let lines: Vec<String> = history.iter().map(|e| e.to_string()).collect();
+            lines.join("\n")
+        }
+        _ if is_v2 => simulation.period().to_string(),
+        _ => {
+            for _ in 0..1000 {
+                simulation.step();
+            }
+            simulation.energy().to_string()
+        }
+    }
+}
+
+fn read_coords() -> (Vec<String>, Vec<Vec<i64>>) {
+    let mut axis_names: Vec<String> = vec![];
+    let mut coords = vec![];
 
     loop {
         let line = read_line().unwrap();
@@ -151,37 +196,48 @@ fn solve(is_v2: bool) -> u64 {
             break;
         }
 
-        let moon_pos = line[1..line.len() - 1].split(", ").collect::<Vec<&str>>();
-        let x = extract_pos(&moon_pos, "x=");
-        let y = extract_pos(&moon_pos, "y=");
-        let z = extract_pos(&moon_pos, "z=");
-        let moon = Moon::new(x, y, z);
-        moons.push(moon);
-    }
+        let (names, values) = parse_coords(&line);
+        if !coords.is_empty() && names != axis_names {
+            panic!(
+                "Expected axes {:?}, found {:?} in {}",
+                axis_names, names, line
+            );
+        }
 
-    let mut moons = Moons::new(moons);
+        axis_names = names;
+        coords.push(values);
+    }
 
-    if is_v2 {
-        // x, y, z cycle is just LCM(x cycle, y cycle, z cycle)
-        while !moons.all_repeats_found() {
-            moons.step();
-        }
+    (axis_names, coords)
+}
 
-        let x_repeat = u64::from(moons.repeat_step[0].unwrap());
-        let y_repeat = u64::from(moons.repeat_step[1].unwrap());
-        let z_repeat = u64::from(moons.repeat_step[2].unwrap());
+fn bodies_from<const N: usize>(coords: &[Vec<i64>]) -> Vec<Body<N>> {
+    coords
+        .iter()
+        .map(|values| {
+            let mut pos = [0; N];
+            pos.copy_from_slice(values);
+            Body::new(pos)
+        })
+        .collect()
+}
 
-        lcm(x_repeat, lcm(y_repeat, z_repeat))
-    } else {
-        for _ in 0..1000 {
-            moons.step();
-        }
-        moons.energy() as u64
-    }
+// Parses `<x=-1, y=0, z=2>` into its axis names and values, keeping the input's axis order
+fn parse_coords(line: &str) -> (Vec<String>, Vec<i64>) {
+    line.trim_start_matches('<')
+        .trim_end_matches('>')
+        .split(",")
+        .map(|part| {
+            let mut pair = part.trim().split("=");
+            let name = pair.next().unwrap().to_string();
+            let value = pair.next().unwrap().parse::<i64>().unwrap();
+            (name, value)
+        })
+        .unzip()
 }
 
 fn lcm(a: u64, b: u64) -> u64 {
-    a * b / gcd(a, b)
+    a / gcd(a, b) * b
 }
 
 fn gcd(a: u64, b: u64) -> u64 {
@@ -202,9 +258,3 @@ fn read_line() -> Result<String, io::Error> {
         Err(error) => Err(error),
     }
 }
-
-fn extract_pos(parts: &Vec<&str>, target: &str) -> i32 {
-    parts.iter().find(|pos| pos.starts_with(target)).unwrap()[2..]
-        .parse::<i32>()
-        .unwrap()
-}
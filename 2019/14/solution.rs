@@ -1,4 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fmt;
 use std::io;
 
 #[derive(Debug, Clone)]
@@ -8,32 +10,20 @@ struct ChemAndAmount {
 }
 
 impl ChemAndAmount {
-    fn fuel_target(amount: u64) -> Self {
-        Self {
-            chem: "FUEL".to_string(),
-            amount,
+    fn from_str(input: &str) -> Result<Self, ReactionError> {
+        let mut parts = input.trim().split(" ");
+        match (
+            parts.next().map(|a| a.parse::<u64>()),
+            parts.next(),
+            parts.next(),
+        ) {
+            (Some(Ok(amount)), Some(chem), None) => Ok(Self {
+                amount,
+                chem: chem.to_string(),
+            }),
+            _ => Err(ReactionError::Malformed(input.to_string())),
         }
     }
-
-    fn from_str(input: &str) -> Self {
-        let mut parts = input.split(" ");
-        let amount = parts.next().unwrap().parse::<u64>().unwrap();
-        let chem = parts.next().unwrap().to_string();
-
-        Self { amount, chem }
-    }
-
-    fn merge(input: Vec<Self>) -> Vec<Self> {
-        let mut output: HashMap<String, Self> = HashMap::new();
-        input.iter().for_each(|caa| {
-            let map_caa = output.entry(caa.chem.clone()).or_insert(ChemAndAmount {
-                chem: caa.chem.clone(),
-                amount: 0,
-            });
-            map_caa.amount += caa.amount;
-        });
-        output.into_iter().map(|(_, v)| v).collect()
-    }
 }
 
 #[derive(Debug)]
@@ -43,126 +33,238 @@ struct Recipe {
 }
 
 impl Recipe {
-    fn from_str(input: &str) -> Self {
+    fn from_str(input: &str) -> Result<Self, ReactionError> {
         let mut sides = input.split(" => ");
-        let lhs = sides.next().unwrap();
-        let lhs_parts = lhs.split(", ");
-        let rhs = sides.next().unwrap();
-
-        let rhs_caa = ChemAndAmount::from_str(&rhs);
-        let lhs_caa_parts = lhs_parts
-            .map(|l| ChemAndAmount::from_str(l))
-            .collect::<Vec<ChemAndAmount>>();
-
-        Self {
-            input: lhs_caa_parts,
-            output: rhs_caa,
+        let (lhs, rhs) = match (sides.next(), sides.next(), sides.next()) {
+            (Some(lhs), Some(rhs), None) => (lhs, rhs),
+            _ => return Err(ReactionError::Malformed(input.to_string())),
+        };
+
+        Ok(Self {
+            input: lhs
+                .split(", ")
+                .map(ChemAndAmount::from_str)
+                .collect::<Result<Vec<ChemAndAmount>, ReactionError>>()?,
+            output: ChemAndAmount::from_str(rhs)?,
+        })
+    }
+
+    // round up int div of a / b == (a + b - 1) / b
+    fn batches_for(&self, target_amount: u64) -> u64 {
+        (target_amount + self.output.amount - 1) / self.output.amount
+    }
+}
+
+#[derive(Debug)]
+enum ReactionError {
+    Malformed(String),
+    DuplicateRecipe(String),
+    UnknownChemical(String),
+    Cycle(Vec<String>),
+}
+
+impl fmt::Display for ReactionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReactionError::Malformed(line) => write!(f, "Malformed reaction: {:?}", line),
+            ReactionError::DuplicateRecipe(chem) => {
+                write!(f, "{} is produced by more than one reaction", chem)
+            }
+            ReactionError::UnknownChemical(chem) => {
+                write!(f, "No reaction produces {}", chem)
+            }
+            ReactionError::Cycle(chems) => write!(f, "Reaction cycle: {}", chems.join(" -> ")),
         }
     }
 }
 
-type RecipeMap = HashMap<String, Recipe>;
+#[derive(Debug, Default, Clone)]
+struct MaterialLine {
+    batches: u64,
+    produced: u64,
+    used: u64,
+}
 
-fn main() {
-    println!("{}", solve(true));
+impl MaterialLine {
+    fn leftover(&self) -> u64 {
+        self.produced - self.used
+    }
 }
 
-fn solve(is_v2: bool) -> u64 {
-    let mut recipes: RecipeMap = HashMap::new();
+#[derive(Debug)]
+struct BillOfMaterials {
+    fuel: u64,
+    ore: u64,
+    lines: BTreeMap<String, MaterialLine>,
+}
 
-    loop {
-        let line = read_line().unwrap();
-        if line == "" {
-            break;
+struct ReactionGraph {
+    recipes: HashMap<String, Recipe>,
+    // Every chemical FUEL depends on, each listed before anything it is made from
+    order: Vec<String>,
+}
+
+impl ReactionGraph {
+    fn parse(lines: &[String]) -> Result<Self, ReactionError> {
+        let mut recipes = HashMap::new();
+        for line in lines {
+            let recipe = Recipe::from_str(line)?;
+            let chem = recipe.output.chem.clone();
+            if recipes.insert(chem.clone(), recipe).is_some() {
+                return Err(ReactionError::DuplicateRecipe(chem));
+            }
         }
 
-        let r = Recipe::from_str(&line);
-        recipes.insert(r.output.chem.clone(), r);
+        let mut graph = Self {
+            recipes,
+            order: vec![],
+        };
+        graph.order = graph.topological_order("FUEL")?;
+        Ok(graph)
     }
 
-    let recipes = recipes;
-    let chem_depth_map = build_chem_depth_map(&recipes);
-
-    if is_v2 {
-        find_fuel_from(1000000000000, &recipes, &chem_depth_map)
-    } else {
-        find_ore_for(1, &recipes, &chem_depth_map)
+    fn topological_order(&self, target: &str) -> Result<Vec<String>, ReactionError> {
+        let mut finished: HashMap<String, bool> = HashMap::new();
+        let mut path = vec![];
+        let mut order = vec![];
+        self.visit(target, &mut finished, &mut path, &mut order)?;
+        order.reverse();
+        Ok(order)
     }
-}
 
-fn find_fuel_from(ore: u64, recipes: &RecipeMap, chem_depth_map: &HashMap<String, u32>) -> u64 {
-    let mut min: u64 = 1;
-    let mut max: u64 = 1000000000;
+    fn visit(
+        &self,
+        chem: &str,
+        finished: &mut HashMap<String, bool>,
+        path: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) -> Result<(), ReactionError> {
+        match finished.get(chem) {
+            Some(true) => return Ok(()),
+            Some(false) => {
+                let start = path.iter().position(|c| c == chem).unwrap();
+                let mut cycle = path[start..].to_vec();
+                cycle.push(chem.to_string());
+                return Err(ReactionError::Cycle(cycle));
+            }
+            None => (),
+        }
 
-    while min <= max {
-        let mid = (min + max) / 2;
-        let ore_used = find_ore_for(mid, recipes, chem_depth_map);
+        if chem == "ORE" {
+            finished.insert(chem.to_string(), true);
+            return Ok(());
+        }
+
+        let recipe = self
+            .recipes
+            .get(chem)
+            .ok_or_else(|| ReactionError::UnknownChemical(chem.to_string()))?;
 
-        if ore_used < ore {
-            min = mid + 1;
-        } else if ore_used > ore {
-            max = mid - 1;
-        } else {
-            return mid
+        finished.insert(chem.to_string(), false);
+        path.push(chem.to_string());
+        for input in recipe.input.iter() {
+            self.visit(&input.chem, finished, path, order)?;
         }
+        path.pop();
+        finished.insert(chem.to_string(), true);
+        order.push(chem.to_string());
+        Ok(())
     }
 
-    max
-}
+    fn bill_of_materials(&self, fuel: u64) -> BillOfMaterials {
+        let mut lines: BTreeMap<String, MaterialLine> = BTreeMap::new();
+        lines.entry("FUEL".to_string()).or_default().used = fuel;
 
-fn find_ore_for(fuel: u64, recipes: &RecipeMap, chem_depth_map: &HashMap<String, u32>) -> u64 {
-    let mut requirements = vec![ChemAndAmount::fuel_target(fuel)];
+        for chem in self.order.iter() {
+            let recipe = &self.recipes[chem];
+            let line = lines.entry(chem.clone()).or_default();
+            line.batches = recipe.batches_for(line.used);
+            line.produced = line.batches * recipe.output.amount;
+            let batches = line.batches;
 
-    loop {
-        let target = requirements.pop().unwrap();
-        let recipe = &recipes[&target.chem];
-
-        let recipe_count = get_required_recipe_count(recipe, target.amount);
-        let new_requirements = recipe.input.iter().map(|r_caa| {
-            let mut adj_caa = r_caa.clone();
-            adj_caa.amount *= recipe_count;
-            adj_caa
-        });
-
-        requirements.extend(new_requirements);
-        requirements = ChemAndAmount::merge(requirements);
-        if requirements.iter().all(|caa| chem_depth_map[&caa.chem] < 1) {
-            break;
+            for input in recipe.input.iter() {
+                lines.entry(input.chem.clone()).or_default().used += input.amount * batches;
+            }
         }
-        requirements.sort_by(|r1, r2| chem_depth_map[&r1.chem].cmp(&chem_depth_map[&r2.chem]));
+
+        let ore = lines.remove("ORE").map_or(0, |line| line.used);
+        BillOfMaterials { fuel, ore, lines }
     }
 
-    requirements[0].amount
-}
+    fn ore_for(&self, fuel: u64) -> u64 {
+        self.bill_of_materials(fuel).ore
+    }
 
-fn get_required_recipe_count(r: &Recipe, target_amount: u64) -> u64 {
-    let output_amount = r.output.amount;
+    fn max_fuel_from(&self, ore: u64) -> u64 {
+        let ore_per_fuel = self.ore_for(1);
+        if ore_per_fuel > ore {
+            return 0;
+        }
 
-    // round up int div of a / b == (a + b - 1) / b
-    (target_amount + output_amount - 1) / output_amount
+        // Leftovers only ever make later fuel cheaper, so a single fuel's ore cost gives a lower bound
+        let mut min = ore / ore_per_fuel;
+        let mut max = min * 2;
+        while self.ore_for(max) <= ore {
+            min = max;
+            max *= 2;
+        }
+
+        while min + 1 < max {
+            let mid = min + (max - min) / 2;
+            if self.ore_for(mid) <= ore {
+                min = mid;
+            } else {
+                max = mid;
+            }
+        }
+
+        min
+    }
 }
 
-fn build_chem_depth_map(recipes: &RecipeMap) -> HashMap<String, u32> {
-    let mut output = HashMap::new();
-    output.insert("ORE".to_owned(), 0);
-    find_depth_of("FUEL", recipes, &mut output);
-    output
+fn main() {
+    println!("{}", solve(true));
 }
 
-fn find_depth_of(
-    chem: &str,
-    recipes: &RecipeMap,
-    chem_depth_map: &mut HashMap<String, u32>,
-) -> u32 {
-    if let Some(depth) = chem_depth_map.get(chem) {
-        return *depth;
+fn solve(is_v2: bool) -> String {
+    let mut lines = vec![];
+
+    loop {
+        let line = read_line().unwrap();
+        if line == "" {
+            break;
+        }
+
+        lines.push(line);
     }
 
-    let input_chems = recipes[chem].input.iter().map(|caa| &caa.chem);
-    let input_chem_depths = input_chems.map(|chem| find_depth_of(chem, recipes, chem_depth_map));
-    let this_depth = input_chem_depths.max().unwrap() + 1;
-    chem_depth_map.insert(chem.to_string(), this_depth);
-    this_depth
+    let graph = match ReactionGraph::parse(&lines) {
+        Ok(graph) => graph,
+        Err(error) => return error.to_string(),
+    };
+
+    if env::args().any(|arg| arg == "--bom") {
+        format_bill_of_materials(&graph.bill_of_materials(1))
+    } else if is_v2 {
+        graph.max_fuel_from(1000000000000).to_string()
+    } else {
+        graph.ore_for(1).to_string()
+    }
+}
+
+fn format_bill_of_materials(bom: &BillOfMaterials) -> String {
+    let mut output = vec![format!("{} FUEL from {} ORE", bom.fuel, bom.ore)];
+    for (chem, line) in bom.lines.iter() {
+        output.push(format!(
+            "{}: {} batches, {} produced, {} used, {} left over",
+            chem,
+            line.batches,
+            line.produced,
+            line.used,
+            line.leftover()
+        ));
+    }
+    output.join("\n")
 }
 
 fn read_line() -> Result<String, io::Error> {
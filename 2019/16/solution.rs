@@ -1,79 +1,88 @@
+use std::env;
 use std::io;
+use std::thread;
 
-enum TransformMapValue {
-    PosOne,
-    PosZero,
-    NegOne,
-    NegZero,
+struct Fft {
+    threads: usize,
 }
 
-impl TransformMapValue {
-    fn next(&self) -> Self {
-        match self {
-            Self::PosOne => Self::PosZero,
-            Self::PosZero => Self::NegOne,
-            Self::NegOne => Self::NegZero,
-            Self::NegZero => Self::PosOne,
+impl Fft {
+    fn new(threads: usize) -> Self {
+        Self {
+            threads: threads.max(1),
         }
     }
 
-    fn to_i8(&self) -> i8 {
-        match self {
-            Self::PosOne => 1,
-            Self::NegOne => -1,
-            Self::PosZero | Self::NegZero => 0,
+    // Runs `phases` phases and returns the signal from `offset` onwards. Each output digit only
+    // depends on itself and later digits, so everything before the offset can be dropped up front.
+    fn run(&self, signal: &[i8], phases: usize, offset: usize) -> Vec<i8> {
+        let mut digs = signal[offset..].to_vec();
+
+        for _ in 0..phases {
+            digs = self.phase(&digs, offset);
         }
+
+        digs
     }
-}
 
-struct TransformMapIter {
-    total_remaining: usize,
-    repeat: usize,
-    curr: TransformMapValue,
-    curr_remaining: usize,
-}
+    fn phase(&self, digs: &[i8], offset: usize) -> Vec<i8> {
+        let mut prefix_sums = vec![0i64; digs.len() + 1];
+        for (i, dig) in digs.iter().enumerate() {
+            prefix_sums[i + 1] = prefix_sums[i] + i64::from(*dig);
+        }
 
-impl TransformMapIter {
-    fn new(length: usize, dig: usize) -> Self {
-        Self {
-            total_remaining: length,
-            repeat: dig + 1,
-            curr: TransformMapValue::NegZero,
-            curr_remaining: dig,
+        if self.threads == 1 {
+            return (0..digs.len())
+                .map(|i| output_digit(&prefix_sums, offset, i))
+                .collect();
         }
+
+        // Early digits cost far more than late ones, so threads take every nth digit to share the load
+        let prefix_sums = &prefix_sums;
+        let chunks: Vec<Vec<i8>> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..self.threads)
+                .map(|t| {
+                    scope.spawn(move || {
+                        (t..digs.len())
+                            .step_by(self.threads)
+                            .map(|i| output_digit(prefix_sums, offset, i))
+                            .collect::<Vec<i8>>()
+                    })
+                })
+                .collect();
+
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        (0..digs.len())
+            .map(|i| chunks[i % self.threads][i / self.threads])
+            .collect()
     }
 }
 
-impl Iterator for TransformMapIter {
-    type Item = i8;
-
-    fn next(&mut self) -> Option<i8> {
-        if self.total_remaining == 0 {
-            return None;
-        }
-
-        if self.curr_remaining == 0 {
-            self.curr = self.curr.next();
-            self.curr_remaining = self.repeat;
+// Pattern for absolute digit n is runs of n + 1 copies of 0, 1, 0, -1 with the very first value
+// skipped, so the +1 runs start at n and repeat every 4 * (n + 1) digits, with -1 runs 2 * (n + 1)
+// after them. Summing each run from the prefix sums makes a phase O(len * log(len)).
+fn output_digit(prefix_sums: &[i64], offset: usize, index: usize) -> i8 {
+    let len = prefix_sums.len() - 1;
+    let run = offset + index + 1;
+    let range_sum = |start: usize| {
+        let start = start - offset;
+        if start >= len {
+            0
+        } else {
+            prefix_sums[(start + run).min(len)] - prefix_sums[start]
         }
+    };
 
-        self.curr_remaining -= 1;
-        self.total_remaining -= 1;
-        Some(self.curr.to_i8())
+    let mut total = 0;
+    let mut start = offset + index;
+    while start - offset < len {
+        total += range_sum(start) - range_sum(start + 2 * run);
+        start += 4 * run;
     }
-}
 
-fn transform(digs: &Vec<i8>) -> Vec<i8> {
-    (0..digs.len())
-        .map(|n_dig| {
-            let new_val = digs
-                .iter()
-                .zip(TransformMapIter::new(digs.len(), n_dig))
-                .map(|(dig, multi)| (dig * multi) as i32)
-                .sum::<i32>();
-            (new_val.abs() % 10) as i8
-        })
-        .collect()
+    (total.abs() % 10) as i8
 }
 
 fn main() {
@@ -82,56 +91,32 @@ fn main() {
 
 fn solve(is_v2: bool) -> String {
     let line = read_line().unwrap();
-    let mut digs: Vec<i8> = line.bytes().map(|b| (b - b'0') as i8).collect();
-
-    if is_v2 {
+    let digs: Vec<i8> = line.bytes().map(|b| (b - b'0') as i8).collect();
+    let threads = env::args()
+        .skip_while(|arg| arg != "--threads")
+        .nth(1)
+        .map_or(1, |threads| threads.parse().unwrap());
+    let fft = Fft::new(threads);
+
+    let output = if is_v2 {
         let offset: usize = digs
             .iter()
             .take(7)
-            .enumerate()
-            .map(|(i, dig)| (*dig as usize) * 10_usize.pow((6 - i) as u32))
-            .sum();
-        if offset < digs.len() * 10000 / 2 {
-            panic!("Shortcut where transform map is all 1s can't be taken");
-        }
-
-        // only need to consider digs after offset as each digit is calculated using itself and future digits only
-        let full_length = digs.len() * 10_000;
-        digs = digs
-            .into_iter()
+            .fold(0, |offset, dig| offset * 10 + *dig as usize);
+        let full_signal: Vec<i8> = digs
+            .iter()
+            .cloned()
             .cycle()
-            .take(full_length)
-            .skip(offset)
+            .take(digs.len() * 10_000)
             .collect();
 
-        // transform map looks like
-        // 1 1 1 1 ...
-        // 0 1 1 1 ...
-        // 0 0 1 1 ...
-        // 0 0 0 1 ...
-        // we can just reverse the array to get
-        // 1 0 0 0 ...
-        // 1 1 0 0 ...
-        // 1 1 1 0 ...
-        // 1 1 1 1 ...
-        // which just means each new digit is the previous new digit plus the old digit
-        // effectively a cumsum
-        digs.reverse();
-
-        for _ in 0..100 {
-            for i in 1..digs.len() {
-                digs[i] = (digs[i] + digs[i - 1]) % 10;
-            }
-        }
-
-        digs.reverse();
+        fft.run(&full_signal, 100, offset)
     } else {
-        for _ in 0..100 {
-            digs = transform(&digs);
-        }
-    }
+        fft.run(&digs, 100, 0)
+    };
 
-    digs.iter()
+    output
+        .iter()
         .take(8)
         .map(|i| i.to_string())
         .collect::<String>()
@@ -1,329 +1,214 @@
-use std::{cmp, io};
-use std::collections::{BTreeSet, HashMap, HashSet};
-//use std::iter::FromIterator;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::io;
 
-#[derive(Eq, PartialEq, Hash, Clone, Copy)]
-struct Point {
-    x: usize,
-    y: usize,
-}
-
-impl Point {
-    fn new(x: usize, y: usize) -> Self {
-        Self { x, y }
-    }
-
-    fn adjacent(&self) -> Vec<Self> {
-        let mut output = Vec::with_capacity(4);
-        output.push(Self { x: self.x + 1, y: self.y });
-        output.push(Self { x: self.x, y: self.y +1 });
-
-        if self.x > 0 {
-            output.push(Self { x: self.x - 1, y: self.y });
-        }
-        if self.y > 0 {
-            output.push(Self { x: self.x, y: self.y - 1 });
-        }
-
-        output
-    }
-}
-
-#[derive(Eq, PartialEq, Clone, Hash)]
-struct VisitNode {
-    pos: char,
-    keys: BTreeSet<char>
-}
-
-impl VisitNode {
-    fn new(pos: char, keys: BTreeSet<char>) -> Self {
-        Self { pos, keys }
-    }
-
-    fn can_access(&self, tile: TileType) -> bool {
-        match tile {
-            TileType::Empty | TileType::Key(_) => true,
-            TileType::Wall => false,
-            TileType::Lock(c) => self.keys.contains(&c),
-        }
-    }
-
-    fn add_key(&mut self, key: char) {
-        self.keys.insert(key);
-    }
-
-    fn has_needed_keys(&self, needed_keys: &BTreeSet<char>) -> bool {
-        needed_keys.difference(&self.keys).count() == 0
-    }
-}
-
-#[derive(Eq, PartialEq, Clone, Hash)]
-struct KeyTargetVisitNode {
-    point: Point,
-    keys_needed: BTreeSet<char>,
-}
-
-impl KeyTargetVisitNode {
-    fn new(point: Point, keys_needed: BTreeSet<char>) -> Self {
-        Self { point, keys_needed }
-    }
-
-    fn add_needed_key(&mut self, key: char) {
-        self.keys_needed.insert(key);
-    }
-}
-
-#[derive(Clone)]
-struct SearchNode {
-    visit_node: VisitNode,
+#[derive(Debug, Clone, Copy)]
+struct KeyPath {
+    key: usize,
     dist: usize,
+    doors: u32,
 }
 
-impl SearchNode {
-    fn new(pos: char, keys: BTreeSet<char>, dist: usize) -> Self {
-        Self { visit_node: VisitNode::new(pos, keys), dist }
-    }
-
-    fn can_access(&self, tile: TileType) -> bool {
-        self.visit_node.can_access(tile)
-    }
-
-    fn add_key(&mut self, key: char) {
-        self.visit_node.add_key(key)
-    }
-
-    fn pos(&self) -> char {
-        self.visit_node.pos
-    }
-
-    fn has_needed_keys(&self, needed_keys: &BTreeSet<char>) -> bool {
-        self.visit_node.has_needed_keys(needed_keys)
-    }
-
-//    fn adjacent(&self) -> Vec<Self> {
-//        self.visit_node.point.adjacent().into_iter().map(|point| Self::new(point, self.visit_node.keys.clone(), self.dist + 1)).collect()
-//    }
+struct Vault {
+    grid: Vec<Vec<char>>,
+    robot_count: usize,
+    all_keys: u32,
+    // Indexed by node: robot starts first, then one node per key letter
+    paths: Vec<Vec<KeyPath>>,
 }
 
-struct KeyTargetSearchNode {
-    visit_node: KeyTargetVisitNode,
-    dist: usize,
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct State {
+    positions: Vec<usize>,
+    keys: u32,
 }
 
-impl KeyTargetSearchNode {
-    fn new(point: Point, keys_needed: BTreeSet<char>, dist: usize) -> Self {
-        Self { visit_node: KeyTargetVisitNode::new(point, keys_needed), dist }
-    }
-
-    fn add_needed_key(&mut self, key: char) {
-        self.visit_node.add_needed_key(key)
-    }
+impl Vault {
+    fn new(mut grid: Vec<Vec<char>>, split_robots: bool) -> Self {
+        if split_robots {
+            split_entrance(&mut grid);
+        }
 
-    fn point(&self) -> &Point {
-        &self.visit_node.point
-    }
+        let mut starts = vec![];
+        let mut key_locs = vec![None; 26];
+        for (y, row) in grid.iter().enumerate() {
+            for (x, c) in row.iter().enumerate() {
+                match c {
+                    '@' => starts.push((x, y)),
+                    'a'..='z' => key_locs[key_index(*c)] = Some((x, y)),
+                    _ => (),
+                }
+            }
+        }
 
-    fn adjacent(&self) -> Vec<Self> {
-        self.visit_node.point.adjacent().into_iter().map(|point| Self::new(point, self.visit_node.keys_needed.clone(), self.dist + 1)).collect()
-    }
-}
+        let all_keys = key_locs
+            .iter()
+            .enumerate()
+            .filter(|(_, loc)| loc.is_some())
+            .fold(0, |mask, (key, _)| mask | 1 << key);
+
+        let mut vault = Self {
+            grid,
+            robot_count: starts.len(),
+            all_keys,
+            paths: vec![],
+        };
 
-#[derive(Copy, Clone)]
-enum TileType {
-    Empty,
-    Wall,
-    Key(char),
-    Lock(char)
-}
+        let nodes: Vec<Option<(usize, usize)>> = starts
+            .into_iter()
+            .map(Some)
+            .chain(key_locs.into_iter())
+            .collect();
+        vault.paths = nodes
+            .iter()
+            .map(|loc| match loc {
+                Some(loc) => vault.find_key_paths(*loc),
+                None => vec![],
+            })
+            .collect();
+
+        vault
+    }
+
+    fn find_key_paths(&self, start: (usize, usize)) -> Vec<KeyPath> {
+        let mut paths = vec![];
+        let mut seen = vec![vec![false; self.grid[0].len()]; self.grid.len()];
+        let mut to_visit = VecDeque::new();
+        to_visit.push_back((start, 0, 0));
+        seen[start.1][start.0] = true;
+
+        while let Some(((x, y), dist, doors)) = to_visit.pop_front() {
+            let mut doors = doors;
+            match self.grid[y][x] {
+                'a'..='z' if dist > 0 => paths.push(KeyPath {
+                    key: key_index(self.grid[y][x]),
+                    dist,
+                    doors,
+                }),
+                'A'..='Z' => doors |= 1 << key_index(self.grid[y][x].to_ascii_lowercase()),
+                _ => (),
+            }
 
-impl TileType {
-    fn from_char(c: char) -> Self {
-        match c {
-            '.' | '@' => Self::Empty,
-            '#' => Self::Wall,
-            'A'..='Z' => Self::Lock(((c as u8) - b'A' + b'a') as char),
-            'a'..='z' => Self::Key(c),
-            _ => panic!("Unknown tile type"),
+            for (nx, ny) in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)].iter() {
+                if self.grid[*ny][*nx] != '#' && !seen[*ny][*nx] {
+                    seen[*ny][*nx] = true;
+                    to_visit.push_back(((*nx, *ny), dist + 1, doors));
+                }
+            }
         }
-    }
-}
 
-#[derive(Clone, Debug)]
-struct TargetKey {
-    key: char,
-    dist: usize,
-    keys_needed: BTreeSet<char>
-}
-
-impl TargetKey {
-    fn new(key: char, dist: usize, keys_needed: BTreeSet<char>) -> Self {
-        Self { key, dist, keys_needed }
+        // Doors without a matching key anywhere in the vault can never be opened, so ignore them
+        for path in paths.iter_mut() {
+            path.doors &= self.all_keys;
+        }
+        paths
     }
-}
 
-struct GameMap {
-    map: HashMap<Point, TileType>,
-    key_locs: HashMap<char, Point>,
-    key_to_key_map: HashMap<char, Vec<TargetKey>>,
-    player_pos: Point,
-}
-
-impl GameMap {
-    fn new() -> Self {
-        Self { map: HashMap::new(), key_to_key_map: HashMap::new(), player_pos: Point::new(0, 0), key_locs: HashMap::new() }
-    }
+    fn min_steps(&self) -> Option<usize> {
+        let start = State {
+            positions: (0..self.robot_count).collect(),
+            keys: 0,
+        };
+        let mut best: HashMap<State, usize> = HashMap::new();
+        let mut to_visit = BinaryHeap::new();
+        best.insert(start.clone(), 0);
+        to_visit.push(Reverse((0, start)));
+
+        while let Some(Reverse((dist, state))) = to_visit.pop() {
+            if state.keys == self.all_keys {
+                return Some(dist);
+            }
+            if best[&state] < dist {
+                continue;
+            }
 
-    fn get_tile(&self, p: &Point) -> TileType {
-        self.map[p]
-    }
+            for (robot, node) in state.positions.iter().enumerate() {
+                for path in self.paths[*node].iter() {
+                    if state.keys & 1 << path.key != 0 || path.doors & !state.keys != 0 {
+                        continue;
+                    }
 
-    fn key_count(&self) -> usize {
-        self.key_locs.len()
-    }
+                    let mut next = state.clone();
+                    next.positions[robot] = self.robot_count + path.key;
+                    next.keys |= 1 << path.key;
+                    let next_dist = dist + path.dist;
 
-    fn add_node(&mut self, x: usize, y: usize, c: char) {
-        let point = Point::new(x, y);
-        let tile = TileType::from_char(c);
-        if c == '@' {
-            self.player_pos = point;
-        }
-        match tile {
-            TileType::Key(c) => { self.key_locs.insert(c, point); },
-            _ => (),
+                    if best.get(&next).map_or(true, |d| next_dist < *d) {
+                        best.insert(next.clone(), next_dist);
+                        to_visit.push(Reverse((next_dist, next)));
+                    }
+                }
+            }
         }
-        self.map.insert(point, tile);
-    }
 
-    fn add_key_to_key_entry(&mut self, from: char, target_key: TargetKey) {
-        if !self.key_to_key_map.contains_key(&from) {
-            self.key_to_key_map.insert(from, vec![]);
-        }
-        self.key_to_key_map.get_mut(&from).unwrap().push(target_key);
+        None
     }
 }
 
-fn main() {
-    println!("{}", solve(false));
+fn key_index(c: char) -> usize {
+    (c as u8 - b'a') as usize
 }
 
-fn solve(is_v2: bool) -> usize {
-    let mut map: GameMap = GameMap::new();
-
-    {
-        let mut y = 0;
-        loop {
-            let line = read_line().unwrap();
-            if line == "" {
-                break;
-            }
-
-            line.chars()
+fn split_entrance(grid: &mut Vec<Vec<char>>) {
+    let entrances: Vec<(usize, usize)> = grid
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
                 .enumerate()
-                .for_each(|(x, c)| map.add_node(x, y, c));
-            y += 1;
+                .filter(|(_, c)| **c == '@')
+                .map(move |(x, _)| (x, y))
+        })
+        .collect();
+
+    // Maps that already have four robots are left alone
+    if entrances.len() != 1 {
+        return;
+    }
+
+    // Only split when the entrance sits in open floor, otherwise the new walls and robots
+    // wouldn't fit inside the map
+    let (x, y) = entrances[0];
+    let open_floor = (0..3).all(|dy| {
+        (0..3).all(|dx| {
+            (dx, dy) == (1, 1)
+                || (y + dy >= 1
+                    && x + dx >= 1
+                    && grid.get(y + dy - 1).and_then(|row| row.get(x + dx - 1)) == Some(&'.'))
+        })
+    });
+    if !open_floor {
+        return;
+    }
+
+    let replacement = ["@#@", "###", "@#@"];
+    for (dy, row) in replacement.iter().enumerate() {
+        for (dx, c) in row.chars().enumerate() {
+            grid[y + dy - 1][x + dx - 1] = c;
         }
     }
-
-    build_key_to_key_map(&mut map);
-    output_key_to_key_map(&map.key_to_key_map);
-
-    let mut to_visit: Vec<SearchNode> = vec![SearchNode::new('@', BTreeSet::new(), 0)];
-    let mut visited: HashSet<VisitNode> = HashSet::new();
-    let mut min_steps = std::usize::MAX;
-//    let mut dist_trav = 0;
-
-    while to_visit.len() > 0 {
-        let node = to_visit.pop().unwrap();
-        if visited.contains(&node.visit_node) {
-            continue
-        }
-
-        visited.insert(node.visit_node.clone());
-
-        if node.visit_node.keys.len() == map.key_count() {
-            min_steps = cmp::min(min_steps, node.dist);
-            println!("Found {} - Min {}", node.dist, min_steps);
-            continue;
-        }
-
-//        if dist_trav < node.dist {
-//            dist_trav = node.dist;
-//            println!("Travelled {} tiles - to visit {}", dist_trav, to_visit.len());
-//        }
-
-        map.key_to_key_map[&node.pos()].iter()
-            .filter(|target_key| !node.visit_node.keys.contains(&target_key.key))
-            .filter(|target_key| node.has_needed_keys(&target_key.keys_needed))
-            .for_each(|target_key| {
-                let mut next_node = node.clone();
-                next_node.visit_node.pos = target_key.key;
-                next_node.add_key(target_key.key);
-                next_node.dist += target_key.dist;
-//                to_visit.insert(0, next_node);
-                to_visit.push(next_node);
-            });
-    };
-
-    min_steps
 }
 
-fn output_key_to_key_map(key_to_key_map: &HashMap<char, Vec<TargetKey>>) {
-    let mut keys: Vec<char> = key_to_key_map.keys().cloned().collect();
-    keys.sort();
-    for key in keys {
-        println!("{}", key);
-        let mut vals = key_to_key_map[&key].clone();
-        vals.sort_by(|a, b| a.key.partial_cmp(&b.key).unwrap());
-        for val in vals {
-            println!("{:?}", val);
-        }
-        println!();
+fn main() {
+    match solve(true) {
+        Some(steps) => println!("{}", steps),
+        None => println!("Not all keys can be collected"),
     }
 }
 
-fn build_key_to_key_map(map: &mut GameMap) {
-    let mut starts: Vec<Point> = map.key_locs.values().cloned().collect();
-    starts.push(map.player_pos.clone());
-
-    for start in starts {
-        let start_char = match map.get_tile(&start) {
-            TileType::Key(c) => c,
-            TileType::Empty => '@',
-            _ => unreachable!(),
-        };
-        let mut to_visit: Vec<KeyTargetSearchNode> = vec![KeyTargetSearchNode::new(start, BTreeSet::new(), 0)];
-        let mut visited: HashSet<KeyTargetVisitNode> = HashSet::new();
-        let mut keys_found: HashSet<char> = HashSet::new();
-        keys_found.insert(start_char);
-
-        while to_visit.len() > 0 {
-            let node = to_visit.pop().unwrap();
-            if visited.contains(&node.visit_node) {
-                continue
-            }
-
-            visited.insert(node.visit_node.clone());
-
-            match map.get_tile(node.point()) {
-                TileType::Wall => continue,
-                TileType::Key(c) if !keys_found.contains(&c) => {
-                    map.add_key_to_key_entry(start_char, TargetKey::new(c, node.dist, node.visit_node.keys_needed.clone()));
-                    keys_found.insert(c);
-                },
-                _ => ()
-            }
+fn solve(is_v2: bool) -> Option<usize> {
+    let mut grid = vec![];
 
-            node.adjacent()
-                .into_iter()
-                .for_each(|mut next_node| {
-                    match map.get_tile(next_node.point()) {
-                        TileType::Lock(c) => next_node.add_needed_key(c),
-                        _ => (),
-                    }
-                    to_visit.insert(0, next_node);
-                });
+    loop {
+        let line = read_line().unwrap();
+        if line == "" {
+            break;
         }
+
+        grid.push(line.chars().collect());
     }
+
+    Vault::new(grid, is_v2).min_steps()
 }
 
 fn read_line() -> Result<String, io::Error> {
@@ -1,5 +1,10 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
 use std::io;
+use std::thread;
 
 struct Computer {
     program: Vec<i64>,
@@ -22,6 +27,14 @@ impl Computer {
         }
     }
 
+    fn reset(&mut self, program: &[i64]) {
+        self.program.clear();
+        self.program.extend_from_slice(program);
+        self.pc = 0;
+        self.relative_offset = 0;
+        self.halted = false;
+    }
+
     fn run_until_input(&mut self, input_val: i64) {
         self.next_input = input_val;
         self.run_until_op(3);
@@ -130,75 +143,291 @@ impl Computer {
     }
 }
 
-fn main() {
-    println!("{}", solve(true));
+struct Beam<'a> {
+    nums: &'a [i64],
+    // Reset for every probe rather than allocated again
+    computer: Computer,
+    probes: usize,
+    probe_cache: HashMap<(i64, i64), bool>,
+    // Pulled x range for each traced row, None when the row has no pulled cells
+    rows: Vec<Option<(i64, i64)>>,
+    last_span: (i64, i64),
 }
 
-fn solve(is_v2: bool) -> i64 {
-    let line = read_line().unwrap();
-    let nums: Vec<i64> = line.split(",").map(|i| i.parse::<i64>().unwrap()).collect();
+impl<'a> Beam<'a> {
+    fn new(nums: &'a [i64]) -> Self {
+        Self {
+            nums,
+            computer: Computer::new(nums.to_vec()),
+            probes: 0,
+            probe_cache: HashMap::new(),
+            rows: vec![],
+            last_span: (0, 0),
+        }
+    }
 
-    if is_v2 {
-        let mut result: Option<i64> = None;
-        let mut min_x = 0;
-        let mut min_y = 0;
-        for limit in 0.. {
-            if limit % 100 == 0 {
-                println!("LIMIT: {}", limit);
-            }
-//            println!("Limit: {}, minx: {}, miny: {}", limit, min_x, min_y);
-            for x in 0..=limit {
-//                if cell_pulled(x, limit, &nums) {
-                    if cell_pulled(x + 99, limit, &nums) && cell_pulled(x, limit + 99, &nums) {
-                        result = Some(x * 10000 + limit);
-                        break;
-                    }
-//                } else {
-//                    min_x = x + 1;
-//                }
-            }
+    fn pulled(&mut self, x: i64, y: i64) -> bool {
+        if let Some(pulled) = self.probe_cache.get(&(x, y)) {
+            return *pulled;
+        }
 
-            if result.is_some() {
-                break;
-            }
+        self.probes += 1;
+        let pulled = probe(&mut self.computer, x, y, self.nums);
+        self.probe_cache.insert((x, y), pulled);
+        pulled
+    }
+
+    fn row(&mut self, y: i64) -> Option<(i64, i64)> {
+        while self.rows.len() as i64 <= y {
+            let next_y = self.rows.len() as i64;
+            let span = self.trace_row(next_y);
+            self.rows.push(span);
+        }
+
+        self.rows[y as usize]
+    }
+
+    fn trace_row(&mut self, y: i64) -> Option<(i64, i64)> {
+        // Both beam edges only ever move right, so each row starts from where the last one ended
+        let (prev_left, prev_right) = self.last_span;
+        let search_limit = prev_right.max(y) * 2 + 10;
 
-            for y in 0..=limit {
-//                if cell_pulled(limit, y, &nums) {
-                    if cell_pulled(limit + 99, y, &nums) && cell_pulled(limit, y + 99, &nums) {
-                        result = Some(limit * 10000 + y);
-                        break;
-                    }
-//                } else {
-//                    min_y = y + 1;
-//                }
+        let mut left = prev_left;
+        while !self.pulled(left, y) {
+            left += 1;
+            if left > search_limit {
+                return None;
             }
+        }
 
-            if result.is_some() {
-                break;
+        let mut right = prev_right.max(left);
+        if !self.pulled(right, y) {
+            right = left;
+        }
+        while self.pulled(right + 1, y) {
+            right += 1;
+        }
+
+        self.last_span = (left, right);
+        Some((left, right))
+    }
+
+    fn affected_in(&mut self, min_x: i64, min_y: i64, max_x: i64, max_y: i64) -> i64 {
+        (min_y..=max_y)
+            .filter_map(|y| self.row(y))
+            .map(|(left, right)| (right.min(max_x) - left.max(min_x) + 1).max(0))
+            .sum()
+    }
+
+    fn closest_fit(&mut self, width: i64, height: i64) -> (i64, i64) {
+        for bottom in (height - 1).. {
+            let top = bottom - height + 1;
+            let (bottom_left, top_span) = match (self.row(bottom), self.row(top)) {
+                (Some((bottom_left, _)), Some(top_span)) => (bottom_left, top_span),
+                _ => continue,
+            };
+
+            if top_span.0 <= bottom_left && bottom_left + width - 1 <= top_span.1 {
+                return (bottom_left, top);
             }
         }
-        result.unwrap();
-        0
-    } else {
-        let mut total_affected = 0;
-        for x in 0..50 {
-            for y in 0..50 {
-                if cell_pulled(x, y, &nums) {
-                    total_affected += 1;
+
+        unreachable!()
+    }
+}
+
+// Exact x / y ratio, kept with a positive denominator
+#[derive(Debug, Clone, Copy)]
+struct Fraction {
+    num: i64,
+    den: i64,
+}
+
+impl Fraction {
+    fn new(num: i64, den: i64) -> Self {
+        let common_factor = gcd(num.abs(), den.abs()).max(1);
+        Self {
+            num: num / common_factor,
+            den: den / common_factor,
+        }
+    }
+}
+
+impl PartialEq for Fraction {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Fraction {}
+
+impl Ord for Fraction {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.num * other.den).cmp(&(other.num * self.den))
+    }
+}
+
+impl PartialOrd for Fraction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Fraction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.num, self.den)
+    }
+}
+
+struct Scan {
+    min_x: i64,
+    min_y: i64,
+    width: i64,
+    height: i64,
+    // Row-major pulled flags for every cell in the rectangle
+    cells: Vec<bool>,
+}
+
+impl Scan {
+    fn run(nums: &[i64], min_x: i64, min_y: i64, width: i64, height: i64, threads: usize) -> Self {
+        let rows: Vec<Vec<Vec<bool>>> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|t| {
+                    scope.spawn(move || {
+                        let mut c = Computer::new(nums.to_vec());
+                        (t as i64..height)
+                            .step_by(threads)
+                            .map(|row| {
+                                (min_x..min_x + width)
+                                    .map(|x| probe(&mut c, x, min_y + row, nums))
+                                    .collect()
+                            })
+                            .collect()
+                    })
+                })
+                .collect();
+
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        let cells = (0..height as usize)
+            .flat_map(|row| rows[row % threads][row / threads].iter().cloned())
+            .collect();
+
+        Self {
+            min_x,
+            min_y,
+            width,
+            height,
+            cells,
+        }
+    }
+
+    fn to_pbm(&self) -> String {
+        let mut output = format!("P1\n{} {}\n", self.width, self.height);
+        for row in self.cells.chunks(self.width as usize) {
+            let cells: Vec<&str> = row.iter().map(|c| if *c { "1" } else { "0" }).collect();
+            output.push_str(&cells.join(" "));
+            output.push('\n');
+        }
+        output
+    }
+
+    // Narrowest (lower, upper) bounds on the x / y slope of the beam's left and right edges that
+    // every fully scanned row agrees with
+    fn slope_bounds(&self) -> Option<((Fraction, Fraction), (Fraction, Fraction))> {
+        let mut left: Option<(Fraction, Fraction)> = None;
+        let mut right: Option<(Fraction, Fraction)> = None;
+        let tighten = |bounds: &mut Option<(Fraction, Fraction)>, low: Fraction, high: Fraction| {
+            *bounds = Some(match bounds {
+                Some((l, h)) => ((*l).max(low), (*h).min(high)),
+                None => (low, high),
+            });
+        };
+
+        for (row, cells) in self.cells.chunks(self.width as usize).enumerate() {
+            let y = self.min_y + row as i64;
+            let first = cells.iter().position(|c| *c);
+            let last = cells.iter().rposition(|c| *c);
+
+            if let (Some(first), Some(last), true) = (first, last, y > 0) {
+                if first > 0 {
+                    let x = self.min_x + first as i64;
+                    tighten(&mut left, Fraction::new(x - 1, y), Fraction::new(x, y));
+                }
+                if last + 1 < cells.len() {
+                    let x = self.min_x + last as i64;
+                    tighten(&mut right, Fraction::new(x, y), Fraction::new(x + 1, y));
                 }
             }
         }
-        total_affected
+
+        match (left, right) {
+            (Some(left), Some(right)) => Some((left, right)),
+            _ => None,
+        }
+    }
+}
+
+fn scan(nums: &[i64], args: &[String]) -> String {
+    let bounds: Vec<i64> = args[..4].iter().map(|a| a.parse().unwrap()).collect();
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let scan = Scan::run(nums, bounds[0], bounds[1], bounds[2], bounds[3], threads);
+    fs::write(&args[4], scan.to_pbm()).unwrap();
+
+    match scan.slope_bounds() {
+        Some(((left_low, left_high), (right_low, right_high))) => format!(
+            "Left edge slope in [{}, {}], right edge slope in [{}, {}]",
+            left_low, left_high, right_low, right_high
+        ),
+        None => "Beam edges not inside the scanned rectangle".to_string(),
+    }
+}
+
+fn main() {
+    println!("{}", solve(true));
+}
+
+fn solve(is_v2: bool) -> String {
+    let line = read_line().unwrap();
+    let nums: Vec<i64> = line.split(",").map(|i| i.parse::<i64>().unwrap()).collect();
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() == 6 && args[0] == "--scan" {
+        return scan(&nums, &args[1..]);
+    }
+
+    let mut beam = Beam::new(&nums);
+
+    let result = if is_v2 {
+        let (x, y) = beam.closest_fit(100, 100);
+        x * 10000 + y
+    } else {
+        beam.affected_in(0, 0, 49, 49)
+    };
+
+    if env::args().any(|arg| arg == "--probes") {
+        println!("Probes: {}", beam.probes);
     }
+
+    result.to_string()
 }
 
-fn cell_pulled(x: i64, y: i64, nums: &Vec<i64>) -> bool {
-    let mut c = Computer::new(nums.clone());
+// Reuses an existing computer's memory instead of allocating a fresh one for every cell
+fn probe(c: &mut Computer, x: i64, y: i64, nums: &[i64]) -> bool {
+    c.reset(nums);
     c.run_until_input(x);
     c.run_until_input(y);
     c.run_until_output() == 1
 }
 
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        return a;
+    }
+
+    gcd(b, a % b)
+}
+
 fn read_line() -> Result<String, io::Error> {
     let mut input = String::new();
     match io::stdin().read_line(&mut input) {
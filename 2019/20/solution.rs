@@ -1,107 +1,233 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::env;
 use std::io;
-use std::collections::{HashMap, HashSet};
 
-#[derive(Eq, PartialEq, Hash, Clone, Debug)]
-struct Point {
-    x: usize,
-    y: usize,
+#[derive(Debug)]
+struct Portal {
+    name: String,
+    outer: bool,
+    point: (usize, usize),
 }
 
-impl Point {
-    fn new(x: usize, y: usize) -> Self {
-        Self { x, y }
-    }
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+    to: usize,
+    dist: usize,
+    depth_change: i64,
 }
 
-#[derive(Eq, PartialEq, Hash, Clone, Debug)]
-struct VisitPoint {
-    point: Point,
-    depth: usize,
+#[derive(Debug)]
+struct Route {
+    steps: usize,
+    // Portal node and level at each stop along the way, starting with AA
+    stops: Vec<(usize, usize)>,
 }
 
-impl VisitPoint {
-    fn new(point: Point, depth: usize) -> Self {
-        Self { point, depth }
-    }
+struct DonutMaze {
+    portals: Vec<Portal>,
+    edges: Vec<Vec<Edge>>,
+    start: usize,
+    finish: usize,
 }
 
-struct AdjPoint {
-    point: Point,
-    depth_change: i8,
-}
+impl DonutMaze {
+    fn parse(raw_map: &[Vec<char>]) -> Self {
+        let height = raw_map.len();
+        let width = raw_map.iter().map(|row| row.len()).max().unwrap();
+        let grid: Vec<Vec<char>> = raw_map
+            .iter()
+            .map(|row| {
+                let mut row = row.clone();
+                row.resize(width, ' ');
+                row
+            })
+            .collect();
+
+        let mut portals = vec![];
+        for y in 0..height {
+            for x in 0..width {
+                if !grid[y][x].is_ascii_uppercase() {
+                    continue;
+                }
 
-impl AdjPoint {
-    fn new(point: Point, depth_change: i8) -> Self {
-        Self { point, depth_change }
-    }
-}
+                let label = if y + 1 < height && grid[y + 1][x].is_ascii_uppercase() {
+                    Some((
+                        (x, y + 1),
+                        format!("{}{}", grid[y][x], grid[y + 1][x]),
+                        true,
+                    ))
+                } else if x + 1 < width && grid[y][x + 1].is_ascii_uppercase() {
+                    Some((
+                        (x + 1, y),
+                        format!("{}{}", grid[y][x], grid[y][x + 1]),
+                        false,
+                    ))
+                } else {
+                    None
+                };
 
-struct Node {
-    adj: Vec<AdjPoint>,
-}
+                if let Some(((x2, y2), name, vertical)) = label {
+                    let candidates = if vertical {
+                        [(x, y.wrapping_sub(1)), (x2, y2 + 1)]
+                    } else {
+                        [(x.wrapping_sub(1), y), (x2 + 1, y2)]
+                    };
+                    let point = candidates
+                        .iter()
+                        .cloned()
+                        .find(|(cx, cy)| *cy < height && *cx < width && grid[*cy][*cx] == '.')
+                        .unwrap_or_else(|| panic!("Can't find cell for portal {}", name));
+                    let outer = point.0 == 2
+                        || point.1 == 2
+                        || point.0 == width - 3
+                        || point.1 == height - 3;
+
+                    portals.push(Portal { name, outer, point });
+                }
+            }
+        }
 
-impl Node {
-    fn new() -> Self {
-        Self { adj: vec![] }
-    }
+        let find_portal = |name: &str| {
+            portals
+                .iter()
+                .position(|p| p.name == name)
+                .unwrap_or_else(|| panic!("Missing portal {}", name))
+        };
+        let start = find_portal("AA");
+        let finish = find_portal("ZZ");
+
+        let mut edges: Vec<Vec<Edge>> = portals
+            .iter()
+            .map(|portal| Self::walk_from(&grid, &portals, portal.point))
+            .collect();
+
+        for (from, portal) in portals.iter().enumerate() {
+            let pair = portals
+                .iter()
+                .position(|p| p.name == portal.name && p.outer != portal.outer);
+            if let Some(to) = pair {
+                edges[from].push(Edge {
+                    to,
+                    dist: 1,
+                    depth_change: if portal.outer { -1 } else { 1 },
+                });
+            }
+        }
 
-    fn add_adj(&mut self, point: AdjPoint) {
-        self.adj.push(point);
+        Self {
+            portals,
+            edges,
+            start,
+            finish,
+        }
     }
-}
-
-struct Map {
-    map: HashMap<Point, Node>,
-    visited: HashSet<VisitPoint>,
-    dist_map: HashMap<VisitPoint, usize>,
-    start: VisitPoint,
-    finish: VisitPoint,
-}
 
-impl Map {
-    fn new() -> Self {
-        let origin_visit_point = VisitPoint::new(Point::new(0, 0), 0);
-        Self { map: HashMap::new(), visited: HashSet::new(), dist_map: HashMap::new(), start: origin_visit_point.clone(), finish: origin_visit_point }
-    }
+    fn walk_from(grid: &[Vec<char>], portals: &[Portal], start: (usize, usize)) -> Vec<Edge> {
+        let mut dists = HashMap::new();
+        let mut to_visit = VecDeque::new();
+        dists.insert(start, 0);
+        to_visit.push_back(start);
+
+        while let Some((x, y)) = to_visit.pop_front() {
+            let dist = dists[&(x, y)];
+            for next in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)].iter() {
+                if grid[next.1][next.0] == '.' && !dists.contains_key(next) {
+                    dists.insert(*next, dist + 1);
+                    to_visit.push_back(*next);
+                }
+            }
+        }
 
-    fn is_visited(&self, vpoint: &VisitPoint) -> bool {
-        self.visited.contains(vpoint)
+        portals
+            .iter()
+            .enumerate()
+            .filter(|(_, portal)| portal.point != start)
+            .filter_map(|(to, portal)| {
+                dists.get(&portal.point).map(|dist| Edge {
+                    to,
+                    dist: *dist,
+                    depth_change: 0,
+                })
+            })
+            .collect()
     }
 
-    fn visit(&mut self, vpoint: VisitPoint) {
-        self.visited.insert(vpoint);
-    }
+    fn solve(&self, recursive: bool, max_depth: usize) -> Option<Route> {
+        let start = (self.start, 0);
+        let mut best: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut previous: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+        let mut to_visit = BinaryHeap::new();
+        best.insert(start, 0);
+        to_visit.push(Reverse((0, start)));
+
+        while let Some(Reverse((dist, state))) = to_visit.pop() {
+            if state == (self.finish, 0) {
+                return Some(self.build_route(dist, state, &previous));
+            }
+            if best[&state] < dist {
+                continue;
+            }
 
-    fn set_dist_to(&mut self, vpoint: VisitPoint, dist: usize) {
-        self.dist_map.insert(vpoint, dist);
-    }
+            let (node, level) = state;
+            for edge in self.edges[node].iter() {
+                let next_level = if recursive {
+                    let next_level = level as i64 + edge.depth_change;
+                    if next_level < 0 || next_level > max_depth as i64 {
+                        continue;
+                    }
+                    next_level as usize
+                } else {
+                    0
+                };
+
+                let next = (edge.to, next_level);
+                let next_dist = dist + edge.dist;
+                if best.get(&next).map_or(true, |d| next_dist < *d) {
+                    best.insert(next, next_dist);
+                    previous.insert(next, state);
+                    to_visit.push(Reverse((next_dist, next)));
+                }
+            }
+        }
 
-    fn get_dist(&self, vpoint: &VisitPoint) -> usize {
-        *self.dist_map.get(vpoint).expect(&format!("No dist for {:?}", vpoint))
+        None
     }
 
-    fn add_adj(&mut self, from: Point, to: Point, depth_change: i8) {
-        if !self.map.contains_key(&from) {
-            self.map.insert(from.clone(), Node::new());
+    fn build_route(
+        &self,
+        steps: usize,
+        finish: (usize, usize),
+        previous: &HashMap<(usize, usize), (usize, usize)>,
+    ) -> Route {
+        let mut stops = vec![finish];
+        while let Some(prev) = previous.get(stops.last().unwrap()) {
+            stops.push(*prev);
         }
-        self.map.get_mut(&from).unwrap().add_adj(AdjPoint::new(to.clone(), depth_change.clone()));
+        stops.reverse();
 
-        if !self.map.contains_key(&to) {
-            self.map.insert(to.clone(), Node::new());
-        }
-        self.map.get_mut(&to).unwrap().add_adj(AdjPoint::new(from, -depth_change));
+        Route { steps, stops }
     }
 
-    fn get_adj(&self, vpoint: &VisitPoint) -> Vec<VisitPoint> {
-        self.map[&vpoint.point].adj.iter()
-            .filter_map(|adj_point| {
-                let new_depth = vpoint.depth as i64 + i64::from(adj_point.depth_change);
-                if new_depth >= 0 {
-                    Some(VisitPoint::new(adj_point.point.clone(), new_depth as usize))
-                } else {
-                    None
-                }
-            }).collect()
+    fn describe_route(&self, route: &Route) -> Vec<String> {
+        route
+            .stops
+            .windows(2)
+            .filter(|pair| {
+                pair[0].0 != pair[1].0
+                    && self.portals[pair[0].0].name == self.portals[pair[1].0].name
+            })
+            .map(|pair| {
+                let portal = &self.portals[pair[0].0];
+                format!(
+                    "{} ({}) level {} -> {}",
+                    portal.name,
+                    if portal.outer { "outer" } else { "inner" },
+                    pair[0].1,
+                    pair[1].1
+                )
+            })
+            .collect()
     }
 }
 
@@ -109,35 +235,25 @@ fn main() {
     println!("{}", solve(true));
 }
 
-fn solve(is_v2: bool) -> usize {
-    let mut map = build_grid(is_v2);
-
-    let mut to_visit = vec![map.start.clone()];
-    while to_visit.len() > 0 {
-        let point = to_visit.pop().unwrap();
-
-        if map.is_visited(&point) {
-            continue;
-        }
-        map.visit(point.clone());
-
-        if point == map.finish {
-            break
-        }
-
-        let dist = map.get_dist(&point);
+fn solve(is_v2: bool) -> String {
+    let maze = DonutMaze::parse(&read_map());
+    // Routes wandering deeper than there are portals are treated as unsolvable
+    let max_depth = maze.portals.len();
 
-        for adj_point in map.get_adj(&point) {
-            map.set_dist_to(adj_point.clone(), dist + 1);
-            to_visit.insert(0, adj_point);
+    match maze.solve(is_v2, max_depth) {
+        Some(route) => {
+            if env::args().any(|arg| arg == "--route") {
+                for hop in maze.describe_route(&route) {
+                    println!("{}", hop);
+                }
+            }
+            route.steps.to_string()
         }
+        None => format!("No route within {} levels", max_depth),
     }
-
-    map.get_dist(&map.finish)
 }
 
-fn build_grid(is_v2: bool) -> Map {
-    let mut output_map = Map::new();
+fn read_map() -> Vec<Vec<char>> {
     let mut raw_map: Vec<Vec<char>> = vec![];
 
     loop {
@@ -149,119 +265,7 @@ fn build_grid(is_v2: bool) -> Map {
         raw_map.push(line.chars().collect());
     }
 
-    let mut gates: HashMap<String, Vec<AdjPoint>> = HashMap::new();
-
-    for (y, row) in raw_map.iter().enumerate() {
-        for (x, cell) in row.iter().enumerate() {
-            match *cell {
-                '.' => {
-                    if y > 0 && raw_map[y - 1][x] == '.' {
-                        output_map.add_adj(Point::new(x, y), Point::new(x, y - 1), 0);
-                    }
-
-                    if x > 0 && raw_map[y][x - 1] == '.' {
-                        output_map.add_adj(Point::new(x, y), Point::new(x - 1, y), 0);
-                    }
-                }
-                'A'..='Z' => {
-                    let gate_details = if y > 0 && ('A'..='Z').contains(&raw_map[y - 1][x]) {
-                        let gate_name = format!("{}{}", raw_map[y - 1][x], cell);
-                        let gate_cell = if y > 1 && raw_map[y - 2][x] == '.' {
-                            let depth_change = if y <= raw_map.len() / 2 {
-                                // in upper half of map and dot above gate
-                                // going deeper
-                                1
-                            } else {
-                                // in lower half of map and dot above gate
-                                // going shallower
-                                -1
-                            };
-                            AdjPoint::new(Point::new(x, y - 2), depth_change)
-                        } else if y < raw_map.len() - 1 && raw_map[y + 1][x] == '.' {
-                            let depth_change = if y <= raw_map.len() / 2 {
-                                // in upper half of map and dot below gate
-                                // going shallower
-                                -1
-                            } else {
-                                // in lower half of map and dot below gate
-                                // going deeper
-                                1
-                            };
-                            AdjPoint::new(Point::new(x, y + 1), depth_change)
-
-                        } else {
-                            panic!("Can't find cell for gate {}", gate_name)
-                        };
-
-                        Some((gate_name, gate_cell))
-                    } else if x > 0 && ('A'..='Z').contains(&raw_map[y][x - 1]) {
-                        let gate_name = format!("{}{}", raw_map[y][x - 1], cell);
-                        let gate_cell = if x > 1 && raw_map[y][x - 2] == '.' {
-                            let depth_change = if x <= row.len() / 2 {
-                                // in left half of map and dot left of gate
-                                // going deeper
-                                1
-                            } else {
-                                // in right half of map and dot left of gate
-                                // going shallower
-                                -1
-                            };
-                            AdjPoint::new(Point::new(x - 2, y), depth_change)
-                        } else if x < row.len() - 1 && raw_map[y][x + 1] == '.' {
-                            let depth_change = if x <= row.len() / 2 {
-                                // in left half of map and dot right of gate
-                                // going shallower
-                                -1
-                            } else {
-                                // in right half of map and dot right of gate
-                                // going deeper
-                                1
-                            };
-                            AdjPoint::new(Point::new(x + 1, y), depth_change)
-                        } else {
-                            panic!("Can't find cell for gate {}", gate_name)
-                        };
-
-                        Some((gate_name, gate_cell))
-                    } else {
-                        None
-                    };
-
-                    if let Some((gate_name, gate_cell)) = gate_details {
-                        if !gates.contains_key(&gate_name) {
-                            gates.insert(gate_name.clone(), vec![]);
-                        }
-                        gates.get_mut(&gate_name).unwrap().push(gate_cell);
-                    }
-                },
-                _ => ()
-            }
-        }
-    }
-
-    gates.into_iter()
-        .for_each(|(gate_name, points)| {
-            let mut point_iter = points.into_iter();
-
-            match gate_name.as_str() {
-                "AA" => output_map.start = VisitPoint::new(point_iter.next().unwrap().point, 0),
-                "ZZ" => output_map.finish = VisitPoint::new(point_iter.next().unwrap().point, 0),
-                _ => {
-                    let first_adj_point = point_iter.next().unwrap();
-                    let second_adj_point = point_iter.next().unwrap();
-                    let depth_change = if is_v2 {
-                        first_adj_point.depth_change.clone()
-                    } else {
-                        0
-                    };
-                    output_map.add_adj(first_adj_point.point, second_adj_point.point, depth_change)
-                },
-            }
-        });
-
-    output_map.set_dist_to(output_map.start.clone(), 0);
-
-    output_map
+    raw_map
 }
 
 fn read_line() -> Result<String, io::Error> {
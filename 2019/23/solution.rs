@@ -0,0 +1,260 @@
+use std::collections::VecDeque;
+use std::io;
+
+const NAT_ADDRESS: i64 = 255;
+
+struct Computer {
+    program: Vec<i64>,
+    pc: usize,
+    inputs: VecDeque<i64>,
+    outputs: Vec<i64>,
+    relative_offset: i64,
+    halted: bool,
+}
+
+impl Computer {
+    fn new(program: Vec<i64>) -> Self {
+        Self {
+            program,
+            pc: 0,
+            inputs: VecDeque::new(),
+            outputs: vec![],
+            relative_offset: 0,
+            halted: false,
+        }
+    }
+
+    // Runs until the program asks for input that hasn't arrived yet, handing it -1 and yielding
+    fn run_until_idle(&mut self) {
+        while !self.halted {
+            let waiting = self.program[self.pc] % 100 == 3 && self.inputs.is_empty();
+            self.step();
+            if waiting {
+                break;
+            }
+        }
+    }
+
+    fn step(&mut self) -> i64 {
+        let inst = self.program[self.pc];
+        let op = inst % 100;
+        let modes: [u8; 3] = [
+            ((inst / 100) % 10) as u8,
+            ((inst / 1000) % 10) as u8,
+            ((inst / 10000) % 10) as u8,
+        ];
+
+        match op {
+            1 | 2 | 7 | 8 => {
+                let val_1 = self.get_val(&modes, 0);
+                let val_2 = self.get_val(&modes, 1);
+
+                let value = match op {
+                    1 => val_1 + val_2,
+                    2 => val_1 * val_2,
+                    7 if val_1 < val_2 => 1,
+                    7 => 0,
+                    8 if val_1 == val_2 => 1,
+                    8 => 0,
+                    _ => unreachable!(),
+                };
+
+                let addr = self.get_addr(&modes, 2);
+                self.program[addr] = value;
+                self.pc += 4;
+            }
+            3 => {
+                let addr = self.get_addr(&modes, 0);
+                self.program[addr] = self.inputs.pop_front().unwrap_or(-1);
+                self.pc += 2;
+            }
+            4 => {
+                let val = self.get_val(&modes, 0);
+                self.outputs.push(val);
+                self.pc += 2;
+            }
+            5 | 6 => {
+                let val_1 = self.get_val(&modes, 0);
+                let val_2 = self.get_val(&modes, 1);
+
+                if op == 5 && val_1 != 0 || op == 6 && val_1 == 0 {
+                    self.pc = val_2 as usize;
+                } else {
+                    self.pc += 3;
+                }
+            }
+            9 => {
+                let val = self.get_val(&modes, 0);
+                self.relative_offset += val;
+                self.pc += 2;
+            }
+            99 => self.halted = true,
+            _ => panic!("Unknown op: {}", op),
+        }
+
+        op
+    }
+
+    fn get_val(&mut self, modes: &[u8; 3], instr_index: usize) -> i64 {
+        let addr = self.get_addr(modes, instr_index);
+        self.program[addr]
+    }
+
+    fn get_addr(&mut self, modes: &[u8; 3], instr_index: usize) -> usize {
+        let pc_index = self.pc + 1 + instr_index;
+        let addr = match modes[instr_index] {
+            0 => self.program[pc_index] as usize,
+            1 => pc_index,
+            2 => (self.program[pc_index] + self.relative_offset) as usize,
+            _ => panic!("Unknown address mode: {}", modes[instr_index]),
+        };
+
+        self.expand_program(addr);
+        addr
+    }
+
+    fn expand_program(&mut self, addr: usize) {
+        if addr >= self.program.len() {
+            self.program.resize(addr + 1, 0);
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct NatReport {
+    first_y: i64,
+    first_repeated_y: i64,
+}
+
+struct Network {
+    computers: Vec<Computer>,
+    nat_packet: Option<(i64, i64)>,
+    first_nat_y: Option<i64>,
+    last_delivered_y: Option<i64>,
+}
+
+impl Network {
+    fn new(program: &[i64], size: usize) -> Self {
+        let computers = (0..size)
+            .map(|address| {
+                let mut c = Computer::new(program.to_vec());
+                c.inputs.push_back(address as i64);
+                c
+            })
+            .collect();
+
+        Self {
+            computers,
+            nat_packet: None,
+            first_nat_y: None,
+            last_delivered_y: None,
+        }
+    }
+
+    // Machines take turns in address order so every run routes packets identically
+    fn run(&mut self) -> NatReport {
+        loop {
+            let mut packets_sent = 0;
+
+            for address in 0..self.computers.len() {
+                self.computers[address].run_until_idle();
+                let outputs: Vec<i64> = self.computers[address].outputs.drain(..).collect();
+
+                for packet in outputs.chunks(3) {
+                    self.route(packet[0], packet[1], packet[2]);
+                    packets_sent += 1;
+                }
+            }
+
+            let idle = packets_sent == 0 && self.computers.iter().all(|c| c.inputs.is_empty());
+            if !idle {
+                continue;
+            }
+
+            let (x, y) = self
+                .nat_packet
+                .expect("Network went idle before the NAT got a packet");
+            if self.last_delivered_y == Some(y) {
+                return NatReport {
+                    first_y: self.first_nat_y.unwrap(),
+                    first_repeated_y: y,
+                };
+            }
+
+            self.last_delivered_y = Some(y);
+            self.route(0, x, y);
+        }
+    }
+
+    fn route(&mut self, address: i64, x: i64, y: i64) {
+        if address == NAT_ADDRESS {
+            self.nat_packet = Some((x, y));
+            self.first_nat_y.get_or_insert(y);
+            return;
+        }
+
+        let inputs = &mut self.computers[address as usize].inputs;
+        inputs.push_back(x);
+        inputs.push_back(y);
+    }
+}
+
+fn main() {
+    println!("{}", solve(true));
+}
+
+fn solve(is_v2: bool) -> i64 {
+    let line = read_line().unwrap();
+    let nums: Vec<i64> = line.split(",").map(|i| i.parse::<i64>().unwrap()).collect();
+    let report = Network::new(&nums, 50).run();
+
+    if is_v2 {
+        report.first_repeated_y
+    } else {
+        report.first_y
+    }
+}
+
+fn read_line() -> Result<String, io::Error> {
+    let mut input = String::new();
+    match io::stdin().read_line(&mut input) {
+        Ok(_) => {
+            input.pop();
+            Ok(input)
+        }
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Announces (address, address + 100) to the NAT once, then echoes every packet it gets to the NAT
+    const ECHO_PROGRAM: [i64; 33] = [
+        3, 100, 1001, 100, 100, 104, 104, 255, 4, 100, 4, 104, 3, 101, 1008, 101, -1, 103, 1005,
+        103, 12, 3, 102, 104, 255, 4, 101, 4, 102, 1105, 1, 12, 99,
+    ];
+
+    #[test]
+    fn nat_reports_first_packet_and_repeated_y() {
+        let report = Network::new(&ECHO_PROGRAM, 50).run();
+
+        assert_eq!(
+            report,
+            NatReport {
+                first_y: 100,
+                first_repeated_y: 149,
+            }
+        );
+    }
+
+    #[test]
+    fn runs_are_deterministic() {
+        let first = Network::new(&ECHO_PROGRAM, 5).run();
+        let second = Network::new(&ECHO_PROGRAM, 5).run();
+
+        assert_eq!(first, second);
+        assert_eq!(first.first_repeated_y, 104);
+    }
+}
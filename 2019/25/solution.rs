@@ -0,0 +1,391 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
+use std::io;
+
+const MAX_STEPS_PER_PROMPT: usize = 10_000_000;
+
+// Items that end the game or hang the droid when picked up
+const TRAP_ITEMS: [&str; 5] = [
+    "escape pod",
+    "giant electromagnet",
+    "infinite loop",
+    "molten lava",
+    "photons",
+];
+
+struct Computer {
+    program: Vec<i64>,
+    pc: usize,
+    next_input: i64,
+    last_output: i64,
+    relative_offset: i64,
+    halted: bool,
+}
+
+impl Computer {
+    fn new(program: Vec<i64>) -> Self {
+        Self {
+            program,
+            pc: 0,
+            next_input: 0,
+            last_output: 0,
+            relative_offset: 0,
+            halted: false,
+        }
+    }
+
+    fn run_until_input_line(&mut self, input: &str) {
+        input.bytes().for_each(|b| self.run_until_input(b as i64));
+        self.run_until_input(b'\n' as i64);
+    }
+
+    // Runs until the program is waiting on input or halts, returning everything printed on the way
+    fn run_until_prompt(&mut self) -> String {
+        let mut output = String::new();
+        let mut steps = 0;
+
+        while !self.halted && self.program[self.pc] % 100 != 3 {
+            if self.step() == 4 {
+                output.push((self.last_output as u8) as char);
+            }
+
+            steps += 1;
+            if steps > MAX_STEPS_PER_PROMPT {
+                panic!("Program stuck after: {}", output);
+            }
+        }
+
+        output
+    }
+
+    fn run_until_input(&mut self, input_val: i64) {
+        self.next_input = input_val;
+        self.run_until_op(3);
+    }
+
+    fn run_until_op(&mut self, op_code: i64) {
+        while !self.halted {
+            let last_op = self.step();
+            if last_op == op_code {
+                break;
+            }
+        }
+    }
+
+    fn step(&mut self) -> i64 {
+        let inst = self.program[self.pc];
+        let op = inst % 100;
+        let modes: [u8; 3] = [
+            ((inst / 100) % 10) as u8,
+            ((inst / 1000) % 10) as u8,
+            ((inst / 10000) % 10) as u8,
+        ];
+
+        match op {
+            1 | 2 | 7 | 8 => {
+                let val_1 = self.get_val(&modes, 0);
+                let val_2 = self.get_val(&modes, 1);
+
+                let value = match op {
+                    1 => val_1 + val_2,
+                    2 => val_1 * val_2,
+                    7 if val_1 < val_2 => 1,
+                    7 => 0,
+                    8 if val_1 == val_2 => 1,
+                    8 => 0,
+                    _ => unreachable!(),
+                };
+
+                let addr = self.get_addr(&modes, 2);
+                self.program[addr] = value;
+                self.pc += 4;
+            }
+            3 => {
+                let addr = self.get_addr(&modes, 0);
+                self.program[addr] = self.next_input;
+                self.pc += 2;
+            }
+            4 => {
+                let val = self.get_val(&modes, 0);
+                self.last_output = val;
+                self.pc += 2;
+            }
+            5 | 6 => {
+                let val_1 = self.get_val(&modes, 0);
+                let val_2 = self.get_val(&modes, 1);
+
+                if op == 5 && val_1 != 0 || op == 6 && val_1 == 0 {
+                    self.pc = val_2 as usize;
+                } else {
+                    self.pc += 3;
+                }
+            }
+            9 => {
+                let val = self.get_val(&modes, 0);
+                self.relative_offset += val;
+                self.pc += 2;
+            }
+            99 => self.halted = true,
+            _ => panic!("Unknown op: {}", op),
+        }
+
+        op
+    }
+
+    fn get_val(&mut self, modes: &[u8; 3], instr_index: usize) -> i64 {
+        let addr = self.get_addr(modes, instr_index);
+        self.program[addr]
+    }
+
+    fn get_addr(&mut self, modes: &[u8; 3], instr_index: usize) -> usize {
+        let pc_index = self.pc + 1 + instr_index;
+        let addr = match modes[instr_index] {
+            0 => self.program[pc_index] as usize,
+            1 => pc_index,
+            2 => (self.program[pc_index] + self.relative_offset) as usize,
+            _ => panic!("Unknown address mode: {}", modes[instr_index]),
+        };
+
+        self.expand_program(addr);
+        addr
+    }
+
+    fn expand_program(&mut self, addr: usize) {
+        if addr >= self.program.len() {
+            self.program.resize(addr + 1, 0);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct RoomView {
+    name: String,
+    doors: Vec<String>,
+    items: Vec<String>,
+}
+
+impl RoomView {
+    // Parses the last room description in the output, as being ejected prints two rooms
+    fn parse(output: &str) -> Self {
+        let mut view = Self::default();
+        let mut section = "";
+
+        for line in output.lines() {
+            if line.starts_with("== ") && line.ends_with(" ==") {
+                view = Self {
+                    name: line[3..line.len() - 3].to_string(),
+                    ..Self::default()
+                };
+                section = "";
+            } else if line.ends_with(':') {
+                section = line;
+            } else if line.starts_with("- ") {
+                match section {
+                    "Doors here lead:" => view.doors.push(line[2..].to_string()),
+                    "Items here:" => view.items.push(line[2..].to_string()),
+                    _ => (),
+                }
+            }
+        }
+
+        view
+    }
+}
+
+struct Droid {
+    computer: Computer,
+    // Room name to a map of door direction to the room it leads to
+    rooms: HashMap<String, HashMap<String, String>>,
+    inventory: Vec<String>,
+    checkpoint: Option<(String, String)>,
+}
+
+impl Droid {
+    fn new(program: Vec<i64>) -> Self {
+        Self {
+            computer: Computer::new(program),
+            rooms: HashMap::new(),
+            inventory: vec![],
+            checkpoint: None,
+        }
+    }
+
+    fn send(&mut self, command: &str) -> String {
+        self.computer.run_until_input_line(command);
+        self.computer.run_until_prompt()
+    }
+
+    fn explore(&mut self, room_output: &str) {
+        let view = RoomView::parse(room_output);
+        self.rooms.entry(view.name.clone()).or_default();
+
+        for item in view.items.iter() {
+            if !TRAP_ITEMS.contains(&item.as_str()) {
+                self.send(&format!("take {}", item));
+                self.inventory.push(item.clone());
+            }
+        }
+
+        for door in view.doors.iter() {
+            if self.rooms[&view.name].contains_key(door) {
+                continue;
+            }
+
+            let output = self.send(door);
+            let next = RoomView::parse(&output);
+
+            // The pressure-sensitive floor throws us straight back when carrying the wrong weight
+            if output.contains("Alert!") {
+                self.checkpoint = Some((view.name.clone(), door.clone()));
+                self.link(&view.name, door, "Pressure-Sensitive Floor");
+                continue;
+            }
+
+            let is_new_room = !self.rooms.contains_key(&next.name);
+            self.link(&view.name, door, &next.name);
+            self.link(&next.name, opposite(door), &view.name);
+            if is_new_room {
+                self.explore(&output);
+            }
+            self.send(opposite(door));
+        }
+    }
+
+    fn link(&mut self, from: &str, door: &str, to: &str) {
+        self.rooms
+            .entry(from.to_string())
+            .or_default()
+            .insert(door.to_string(), to.to_string());
+    }
+
+    fn path_between(&self, from: &str, to: &str) -> Vec<String> {
+        let mut previous: HashMap<&str, (&str, &str)> = HashMap::new();
+        let mut seen: HashSet<&str> = HashSet::new();
+        let mut to_visit = VecDeque::new();
+        to_visit.push_back(from);
+        seen.insert(from);
+
+        while let Some(room) = to_visit.pop_front() {
+            if room == to {
+                break;
+            }
+
+            for (door, next) in self.rooms[room].iter() {
+                if self.rooms.contains_key(next) && seen.insert(next) {
+                    previous.insert(next, (room, door));
+                    to_visit.push_back(next);
+                }
+            }
+        }
+
+        let mut path = vec![];
+        let mut room = to;
+        while let Some((prev, door)) = previous.get(room) {
+            path.push(door.to_string());
+            room = prev;
+        }
+        path.reverse();
+        path
+    }
+
+    // Gray-code order means each attempt only takes or drops a single item
+    fn pass_checkpoint(&mut self, door: &str) -> Option<String> {
+        let items = self.inventory.clone();
+        for item in items.iter() {
+            self.send(&format!("drop {}", item));
+        }
+
+        let mut held = 0u64;
+        for i in 0..(1u64 << items.len()) {
+            let gray = i ^ (i >> 1);
+            let changed = held ^ gray;
+            if changed != 0 {
+                let item = &items[changed.trailing_zeros() as usize];
+                let action = if gray & changed != 0 { "take" } else { "drop" };
+                self.send(&format!("{} {}", action, item));
+                held = gray;
+            }
+
+            let output = self.send(door);
+            if !output.contains("Alert!") {
+                return Some(output);
+            }
+        }
+
+        None
+    }
+}
+
+fn opposite(door: &str) -> &str {
+    match door {
+        "north" => "south",
+        "south" => "north",
+        "east" => "west",
+        "west" => "east",
+        _ => panic!("Unknown door: {}", door),
+    }
+}
+
+fn main() {
+    println!("{}", solve());
+}
+
+fn solve() -> String {
+    let line = read_line().unwrap();
+    let nums: Vec<i64> = line.split(",").map(|i| i.parse::<i64>().unwrap()).collect();
+    let mut droid = Droid::new(nums);
+
+    if env::args().any(|arg| arg == "--manual") {
+        return play_manually(&mut droid);
+    }
+
+    let start_output = droid.computer.run_until_prompt();
+    let start = RoomView::parse(&start_output).name;
+    droid.explore(&start_output);
+
+    let (checkpoint, door) = match droid.checkpoint.clone() {
+        Some(checkpoint) => checkpoint,
+        None => return "No security checkpoint found".to_string(),
+    };
+    for step in droid.path_between(&start, &checkpoint) {
+        droid.send(&step);
+    }
+
+    match droid.pass_checkpoint(&door) {
+        Some(output) => output
+            .lines()
+            .find(|line| line.contains("typing"))
+            .and_then(|line| {
+                line.split_whitespace()
+                    .find(|word| word.parse::<u64>().is_ok())
+            })
+            .unwrap_or(output.trim())
+            .to_string(),
+        None => format!("No combination of {:?} passes", droid.inventory),
+    }
+}
+
+fn play_manually(droid: &mut Droid) -> String {
+    print!("{}", droid.computer.run_until_prompt());
+
+    while !droid.computer.halted {
+        let command = read_line().unwrap();
+        if command == "" {
+            break;
+        }
+        print!("{}", droid.send(&command));
+    }
+
+    String::new()
+}
+
+fn read_line() -> Result<String, io::Error> {
+    let mut input = String::new();
+    match io::stdin().read_line(&mut input) {
+        Ok(_) => {
+            input.pop();
+            Ok(input)
+        }
+        Err(error) => Err(error),
+    }
+}
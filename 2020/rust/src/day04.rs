@@ -1,73 +1,326 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::ops::RangeInclusive;
 
-#[aoc(day4, part1)]
-fn solve_part1(input: &str) -> usize {
-    input.split("\n\n").filter(|passport| {
-        passport.contains("ecl:") && passport.contains("pid:") &&
-            passport.contains("eyr:") && passport.contains("hcl:") &&
-            passport.contains("byr:") && passport.contains("iyr:") &&
-            passport.contains("hgt:")
-    }).count()
+// A fixed-length pattern made of literal chars and `[a-z0-9]` style classes, each optionally
+// repeated with `{n}`
+struct Pattern {
+    source: &'static str,
+    parts: Vec<(Vec<RangeInclusive<char>>, usize)>,
 }
 
-#[aoc(day4, part2)]
-fn solve_part2(input: &str) -> usize {
-    input.split("\n\n").filter(|raw_passport| {
-        let tokens = raw_passport.split_whitespace().flat_map(|x| x.split(':'));
-        let passport: HashMap<&str, &str> = tokens.clone().step_by(2)
-            .zip(tokens.skip(1).step_by(2))
-            .collect();
+impl Pattern {
+    fn parse(source: &'static str) -> Self {
+        let mut parts = vec![];
+        let mut chars = source.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            let class = if c == '[' {
+                let mut class = vec![];
+                while let Some(start) = chars.next() {
+                    if start == ']' {
+                        break;
+                    }
+                    if chars.peek() == Some(&'-') {
+                        chars.next();
+                        class.push(start..=chars.next().unwrap());
+                    } else {
+                        class.push(start..=start);
+                    }
+                }
+                class
+            } else {
+                vec![c..=c]
+            };
+
+            let mut count = 1;
+            if chars.peek() == Some(&'{') {
+                chars.next();
+                count = chars
+                    .by_ref()
+                    .take_while(|c| *c != '}')
+                    .collect::<String>()
+                    .parse()
+                    .unwrap();
+            }
+
+            parts.push((class, count));
+        }
+
+        Self { source, parts }
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        let mut chars = value.chars();
+        let all_parts_match = self.parts.iter().all(|(class, count)| {
+            (0..*count).all(|_| {
+                chars
+                    .next()
+                    .is_some_and(|c| class.iter().any(|range| range.contains(&c)))
+            })
+        });
+
+        all_parts_match && chars.next().is_none()
+    }
+}
+
+enum Constraint {
+    Present,
+    IntRange(RangeInclusive<u64>),
+    UnitRange(Vec<(&'static str, RangeInclusive<u64>)>),
+    Pattern(Pattern),
+    OneOf(Vec<&'static str>),
+}
+
+impl Constraint {
+    fn check(&self, value: &str) -> Result<(), FieldError> {
+        match self {
+            Constraint::Present => Ok(()),
+            Constraint::IntRange(range) => check_range(value, range),
+            Constraint::UnitRange(units) => units
+                .iter()
+                .find(|(unit, _)| value.ends_with(unit))
+                .ok_or(FieldError::UnknownUnit)
+                .and_then(|(unit, range)| check_range(&value[..value.len() - unit.len()], range)),
+            Constraint::Pattern(pattern) => {
+                if pattern.matches(value) {
+                    Ok(())
+                } else {
+                    Err(FieldError::NoMatch(pattern.source))
+                }
+            }
+            Constraint::OneOf(options) => {
+                if options.contains(&value) {
+                    Ok(())
+                } else {
+                    Err(FieldError::NotAnOption)
+                }
+            }
+        }
+    }
+}
+
+fn check_range(value: &str, range: &RangeInclusive<u64>) -> Result<(), FieldError> {
+    let number = value.parse::<u64>().map_err(|_| FieldError::NotANumber)?;
+    if range.contains(&number) {
+        Ok(())
+    } else {
+        Err(FieldError::OutOfRange(number, range.clone()))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum FieldError {
+    Missing,
+    NotANumber,
+    OutOfRange(u64, RangeInclusive<u64>),
+    UnknownUnit,
+    NoMatch(&'static str),
+    NotAnOption,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldError::Missing => write!(f, "missing"),
+            FieldError::NotANumber => write!(f, "not a number"),
+            FieldError::OutOfRange(number, range) => write!(
+                f,
+                "{} is outside {}..={}",
+                number,
+                range.start(),
+                range.end()
+            ),
+            FieldError::UnknownUnit => write!(f, "unknown unit"),
+            FieldError::NoMatch(pattern) => write!(f, "doesn't match {}", pattern),
+            FieldError::NotAnOption => write!(f, "not an allowed value"),
+        }
+    }
+}
+
+struct Schema {
+    fields: Vec<(&'static str, Constraint)>,
+}
+
+impl Schema {
+    fn empty() -> Self {
+        Self { fields: vec![] }
+    }
 
-        validate_year(&passport.get("byr"), 1920, 2002) &&
-            validate_year(&passport.get("iyr"), 2010, 2020) &&
-            validate_year(&passport.get("eyr"), 2020, 2030) &&
-            validate_height(&passport.get("hgt")) &&
-            validate_hair_color(&passport.get("hcl")) &&
-            validate_eye_color(&passport.get("ecl")) &&
-            validate_passport_id(&passport.get("pid"))
-    }).count()
+    fn standard() -> Self {
+        Self::empty()
+            .require("byr", Constraint::IntRange(1920..=2002))
+            .require("iyr", Constraint::IntRange(2010..=2020))
+            .require("eyr", Constraint::IntRange(2020..=2030))
+            .require(
+                "hgt",
+                Constraint::UnitRange(vec![("cm", 150..=193), ("in", 59..=76)]),
+            )
+            .require("hcl", Constraint::Pattern(Pattern::parse("#[0-9a-f]{6}")))
+            .require(
+                "ecl",
+                Constraint::OneOf(vec!["amb", "blu", "brn", "gry", "grn", "hzl", "oth"]),
+            )
+            .require("pid", Constraint::Pattern(Pattern::parse("[0-9]{9}")))
+    }
+
+    // Same required fields with every value accepted
+    fn presence_only(self) -> Self {
+        Self {
+            fields: self
+                .fields
+                .into_iter()
+                .map(|(name, _)| (name, Constraint::Present))
+                .collect(),
+        }
+    }
+
+    fn require(mut self, name: &'static str, constraint: Constraint) -> Self {
+        self.fields.push((name, constraint));
+        self
+    }
+
+    fn validate(&self, passport: &HashMap<&str, &str>) -> PassportReport {
+        PassportReport {
+            fields: self
+                .fields
+                .iter()
+                .map(|(name, constraint)| {
+                    let result = passport
+                        .get(name)
+                        .ok_or(FieldError::Missing)
+                        .and_then(|value| constraint.check(value));
+                    (*name, result)
+                })
+                .collect(),
+        }
+    }
+}
+
+struct PassportReport {
+    fields: Vec<(&'static str, Result<(), FieldError>)>,
 }
 
-fn validate_year(value_opt: &Option<&&str>, min: usize, max: usize) -> bool {
-    validate_number(value_opt, min, max)
+impl PassportReport {
+    fn is_valid(&self) -> bool {
+        self.fields.iter().all(|(_, result)| result.is_ok())
+    }
 }
 
-fn validate_height(value_opt: &Option<&&str>) -> bool {
-    value_opt.map(|v| {
-        if v.ends_with("cm") {
-            validate_number(&Some(&&v[0..v.len() - 2]), 150, 193)
-        } else if v.ends_with("in") {
-            validate_number(&Some(&&v[0..v.len() - 2]), 59, 76)
-        } else {
-            false
+impl fmt::Display for PassportReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_valid() {
+            return write!(f, "valid");
         }
-    }).unwrap_or(false)
+
+        let failures: Vec<String> = self
+            .fields
+            .iter()
+            .filter_map(|(name, result)| {
+                result
+                    .as_ref()
+                    .err()
+                    .map(|error| format!("{} {}", name, error))
+            })
+            .collect();
+        write!(f, "invalid: {}", failures.join(", "))
+    }
+}
+
+fn parse_passports(input: &str) -> Vec<HashMap<&str, &str>> {
+    input
+        .split("\n\n")
+        .map(|raw_passport| {
+            raw_passport
+                .split_whitespace()
+                .filter_map(|field| {
+                    let mut parts = field.splitn(2, ':');
+                    Some((parts.next()?, parts.next()?))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// One report per passport, in input order
+fn validate_all(input: &str, schema: &Schema) -> Vec<PassportReport> {
+    parse_passports(input)
+        .iter()
+        .map(|passport| schema.validate(passport))
+        .collect()
+}
+
+fn count_valid(input: &str, schema: &Schema) -> usize {
+    validate_all(input, schema)
+        .iter()
+        .filter(|report| report.is_valid())
+        .count()
 }
 
-fn validate_hair_color(value_opt: &Option<&&str>) -> bool {
-    value_opt
-        .filter(|value| value.len() == 7 && value.starts_with("#") &&
-            value.chars().skip(1)
-                .all(|c| ('0'..='9').contains(&c) || ('a'..='f').contains(&c)))
-        .is_some()
+#[aoc(day4, part1)]
+fn solve_part1(input: &str) -> usize {
+    count_valid(input, &Schema::standard().presence_only())
 }
 
-fn validate_eye_color(value_opt: &Option<&&str>) -> bool {
-    value_opt
-        .filter(|value| ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"].contains(value))
-        .is_some()
+#[aoc(day4, part2)]
+fn solve_part2(input: &str) -> usize {
+    count_valid(input, &Schema::standard())
 }
 
-fn validate_passport_id(value_opt: &Option<&&str>) -> bool {
-    value_opt
-        .filter(|value| value.len() == 9 && value.chars().all(|v| v.is_ascii_digit()))
-        .is_some()
+#[aoc(day4, part2, report)]
+fn solve_part2_report(input: &str) -> String {
+    let lines: Vec<String> = validate_all(input, &Schema::standard())
+        .iter()
+        .enumerate()
+        .map(|(index, report)| format!("{}: {}", index, report))
+        .collect();
+
+    lines.join("\n")
 }
 
-fn validate_number(value_opt: &Option<&&str>, min: usize, max: usize) -> bool {
-    value_opt
-        .map(|value| value.parse::<usize>().map_or(None, |v| Some(v)))
-        .flatten()
-        .map(|value| min <= value && value <= max)
-        .unwrap_or(false)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVALID: &str = "eyr:1972 cid:100
+hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926
+
+iyr:2019
+hcl:#602927 eyr:1967 hgt:170cm
+ecl:grn pid:012533040 byr:1946
+
+hcl:dab227 iyr:2012
+ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277
+
+hgt:59cm ecl:zzz
+eyr:2038 hcl:74454a iyr:2023
+pid:3556412378 byr:2007";
+
+    #[test]
+    fn reports_why_each_passport_failed() {
+        let reports: Vec<String> = validate_all(INVALID, &Schema::standard())
+            .iter()
+            .map(|report| report.to_string())
+            .collect();
+
+        assert_eq!(
+            reports,
+            vec![
+                "invalid: eyr 1972 is outside 2020..=2030, hgt unknown unit, \
+                 pid doesn't match [0-9]{9}",
+                "invalid: eyr 1967 is outside 2020..=2030",
+                "invalid: hcl doesn't match #[0-9a-f]{6}",
+                "invalid: byr 2007 is outside 1920..=2002, iyr 2023 is outside 2010..=2020, \
+                 eyr 2038 is outside 2020..=2030, hgt 59 is outside 150..=193, \
+                 hcl doesn't match #[0-9a-f]{6}, ecl not an allowed value, \
+                 pid doesn't match [0-9]{9}",
+            ]
+        );
+    }
+
+    #[test]
+    fn presence_only_schema_ignores_values() {
+        assert_eq!(count_valid(INVALID, &Schema::standard().presence_only()), 4);
+        assert_eq!(count_valid(INVALID, &Schema::standard()), 0);
+    }
 }
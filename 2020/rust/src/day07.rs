@@ -1,77 +1,254 @@
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 
-struct BagContainer {
-    bag: String,
-    count: usize,
+const TARGET: &str = "shiny gold";
+
+#[derive(Debug)]
+enum BagError {
+    Malformed(String),
+    UnknownBag(String),
+    Cycle(Vec<String>),
 }
 
-#[aoc_generator(day7)]
-fn input_generator(input: &str) -> [HashMap<String, Vec<BagContainer>>; 2] {
-    let mut forward_map: HashMap<String, Vec<BagContainer>> = HashMap::new();
-    let mut back_map: HashMap<String, Vec<BagContainer>> = HashMap::new();
-
-    input
-        .lines()
-        .for_each(|line| {
-            let mut line_parts = line.split(" bags contain ");
-            let source = line_parts.next().unwrap().to_string();
-            let dests = line_parts.next().unwrap();
-
-            if dests != "no other bags." {
-                dests.split(", ").for_each(|dest| {
-                    let mut dest_parts = dest.split_whitespace();
-                    let count = dest_parts.next().unwrap().parse().unwrap();
-                    let bag = dest_parts.take(2).collect::<Vec<&str>>().join(" ");
-
-                    forward_map.entry(source.clone()).or_insert(vec![]).push(BagContainer { bag: bag.clone(), count });
-                    back_map.entry(bag).or_insert(vec![]).push(BagContainer { bag: source.clone(), count });
-                });
-            }
-        });
+impl fmt::Display for BagError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BagError::Malformed(line) => write!(f, "Malformed rule: {:?}", line),
+            BagError::UnknownBag(bag) => write!(f, "No rule mentions {} bags", bag),
+            BagError::Cycle(bags) => write!(f, "Bags contain themselves: {}", bags.join(" -> ")),
+        }
+    }
+}
 
-    [forward_map, back_map]
+struct BagGraph {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    // (bag, count) pairs each bag directly holds
+    contents: Vec<Vec<(usize, usize)>>,
+    // Bags that directly hold each bag
+    containers: Vec<Vec<usize>>,
+    // Every bag, each listed before anything it holds
+    order: Vec<usize>,
+    // Total number of bags inside each bag, filled in from the innermost bags outwards
+    inside_counts: Vec<usize>,
 }
 
-#[aoc(day7, part1)]
-fn solve_part1(maps: &[HashMap<String, Vec<BagContainer>>]) -> usize {
-    let map = maps.get(1).unwrap(); // back_map
-    let mut stack: Vec<String> = vec!["shiny gold".to_string()];
-    let mut seen: HashSet<String> = HashSet::new();
-
-    while !stack.is_empty() {
-        let next = stack.pop().unwrap();
-        if seen.contains(&next) {
-            continue
+impl BagGraph {
+    fn parse(input: &str) -> Result<Self, BagError> {
+        let mut graph = Self {
+            names: vec![],
+            ids: HashMap::new(),
+            contents: vec![],
+            containers: vec![],
+            order: vec![],
+            inside_counts: vec![],
+        };
+
+        for line in input.lines() {
+            let mut line_parts = line.trim_end_matches('.').split(" bags contain ");
+            let (source, dests) = match (line_parts.next(), line_parts.next()) {
+                (Some(source), Some(dests)) => (graph.intern(source), dests),
+                _ => return Err(BagError::Malformed(line.to_string())),
+            };
+            if dests == "no other bags" {
+                continue;
+            }
+
+            for dest in dests.split(", ") {
+                let mut dest_parts = dest.splitn(2, ' ');
+                let count = dest_parts.next().and_then(|count| count.parse().ok());
+                let bag = dest_parts
+                    .next()
+                    .map(|bag| bag.trim_end_matches(" bags").trim_end_matches(" bag"));
+
+                match (count, bag) {
+                    (Some(count), Some(bag)) => {
+                        let bag = graph.intern(bag);
+                        graph.contents[source].push((bag, count));
+                        graph.containers[bag].push(source);
+                    }
+                    _ => return Err(BagError::Malformed(line.to_string())),
+                }
+            }
+        }
+
+        graph.order = graph.topological_order()?;
+        graph.inside_counts = graph.count_all_inside();
+        Ok(graph)
+    }
+
+    fn intern(&mut self, name: &str) -> usize {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        self.contents.push(vec![]);
+        self.containers.push(vec![]);
+        id
+    }
+
+    fn id(&self, name: &str) -> Result<usize, BagError> {
+        self.ids
+            .get(name)
+            .cloned()
+            .ok_or_else(|| BagError::UnknownBag(name.to_string()))
+    }
+
+    fn topological_order(&self) -> Result<Vec<usize>, BagError> {
+        let mut finished = vec![None; self.names.len()];
+        let mut path = vec![];
+        let mut order = vec![];
+        for bag in 0..self.names.len() {
+            self.visit(bag, &mut finished, &mut path, &mut order)?;
         }
-        seen.insert(next.clone());
+        order.reverse();
+        Ok(order)
+    }
 
-        for bags in map.get(&next) {
-            for bag in bags {
-                stack.push(bag.bag.clone())
+    fn visit(
+        &self,
+        bag: usize,
+        finished: &mut [Option<bool>],
+        path: &mut Vec<usize>,
+        order: &mut Vec<usize>,
+    ) -> Result<(), BagError> {
+        match finished[bag] {
+            Some(true) => return Ok(()),
+            Some(false) => {
+                let start = path.iter().position(|b| *b == bag).unwrap();
+                let mut cycle: Vec<String> =
+                    path[start..].iter().map(|b| self.names[*b].clone()).collect();
+                cycle.push(self.names[bag].clone());
+                return Err(BagError::Cycle(cycle));
             }
+            None => (),
+        }
+
+        finished[bag] = Some(false);
+        path.push(bag);
+        for (inner, _) in self.contents[bag].iter() {
+            self.visit(*inner, finished, path, order)?;
         }
+        path.pop();
+        finished[bag] = Some(true);
+        order.push(bag);
+        Ok(())
     }
 
-    seen.len() - 1
-}
+    fn count_all_inside(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.names.len()];
+        for bag in self.order.iter().rev() {
+            counts[*bag] = self.contents[*bag]
+                .iter()
+                .map(|(inner, count)| count * (1 + counts[*inner]))
+                .sum();
+        }
+        counts
+    }
 
-#[aoc(day7, part2)]
-fn solve_part2(maps: &[HashMap<String, Vec<BagContainer>>]) -> usize {
-    let map = maps.get(0).unwrap(); // forward_map
-    let mut stack: Vec<BagContainer> = vec![BagContainer { bag: "shiny gold".to_string(), count: 1 }];
-    let mut total = 0;
+    fn count_inside(&self, name: &str) -> Result<usize, BagError> {
+        Ok(self.inside_counts[self.id(name)?])
+    }
 
-    while !stack.is_empty() {
-        let next = stack.pop().unwrap();
+    // Every bag that holds the named bag at any depth
+    fn eventual_containers(&self, name: &str) -> Result<HashSet<&str>, BagError> {
+        let mut stack = vec![self.id(name)?];
+        let mut seen = HashSet::new();
 
-        total += next.count;
+        while let Some(bag) = stack.pop() {
+            for container in self.containers[bag].iter() {
+                if seen.insert(*container) {
+                    stack.push(*container);
+                }
+            }
+        }
 
-        for bags in map.get(&next.bag) {
-            for bag in bags {
-                stack.push(BagContainer { bag: bag.bag.clone(), count: next.count * bag.count })
+        Ok(seen.into_iter().map(|bag| self.names[bag].as_str()).collect())
+    }
+
+    // Graphviz DOT export, outermost bags first
+    fn to_dot(&self) -> String {
+        let mut output = vec!["digraph bags {".to_string()];
+        for bag in self.order.iter() {
+            output.push(format!("    \"{}\";", self.names[*bag]));
+            for (inner, count) in self.contents[*bag].iter() {
+                output.push(format!(
+                    "    \"{}\" -> \"{}\" [label={}];",
+                    self.names[*bag], self.names[*inner], count
+                ));
             }
         }
+        output.push("}".to_string());
+        output.join("\n")
+    }
+}
+
+#[aoc_generator(day7)]
+fn input_generator(input: &str) -> BagGraph {
+    BagGraph::parse(input).unwrap_or_else(|error| panic!("{}", error))
+}
+
+#[aoc(day7, part1)]
+fn solve_part1(graph: &BagGraph) -> usize {
+    graph.eventual_containers(TARGET).unwrap().len()
+}
+
+#[aoc(day7, part2)]
+fn solve_part2(graph: &BagGraph) -> usize {
+    graph.count_inside(TARGET).unwrap()
+}
+
+#[aoc(day7, part1, dot)]
+fn solve_part1_dot(graph: &BagGraph) -> String {
+    graph.to_dot()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "light red bags contain 1 bright white bag, 2 muted yellow bags.
+dark orange bags contain 3 bright white bags, 4 muted yellow bags.
+bright white bags contain 1 shiny gold bag.
+muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.
+dark olive bags contain 3 faded blue bags, 4 dotted black bags.
+vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
+faded blue bags contain no other bags.
+dotted black bags contain no other bags.";
+
+    #[test]
+    fn counts_containers_and_contents() {
+        let graph = BagGraph::parse(EXAMPLE).unwrap();
+
+        assert_eq!(graph.eventual_containers(TARGET).unwrap().len(), 4);
+        assert_eq!(graph.count_inside(TARGET).unwrap(), 32);
     }
 
-    total - 1
+    #[test]
+    fn exports_edges_outermost_first() {
+        let dot = BagGraph::parse("a b bags contain 2 c d bags.\nc d bags contain no other bags.")
+            .unwrap()
+            .to_dot();
+
+        assert_eq!(
+            dot,
+            "digraph bags {\n    \"a b\";\n    \"a b\" -> \"c d\" [label=2];\n    \"c d\";\n}"
+        );
+    }
+
+    #[test]
+    fn rejects_cyclic_rules() {
+        let error = BagGraph::parse("a b bags contain 1 c d bag.\nc d bags contain 2 a b bags.")
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            "Bags contain themselves: a b -> c d -> a b"
+        );
+    }
 }
@@ -1,48 +1,125 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet, VecDeque};
 
-struct Console {
-    pc: usize,
-    seen_pcs: HashSet<usize>,
-    acc: i32,
-    rom: Vec<Instruction>,
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExitReason {
+    Terminated,
+    Looped,
+    OutOfBounds,
 }
 
-impl Console {
-    fn new(rom: Vec<Instruction>) -> Self {
+#[derive(Default)]
+struct Registers {
+    pc: i64,
+    acc: i64,
+}
+
+type Handler = fn(&mut Registers, i32);
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Operation {
+    Nop,
+    Acc,
+    Jmp,
+}
+
+struct OpSpec {
+    handler: Handler,
+    // Operation this one can be swapped with when patching
+    flip: Option<Operation>,
+}
+
+struct InstructionTable {
+    specs: HashMap<Operation, OpSpec>,
+}
+
+impl InstructionTable {
+    fn empty() -> Self {
         Self {
-            pc: 0,
-            seen_pcs: HashSet::new(),
-            acc: 0,
-            rom,
+            specs: HashMap::new(),
         }
     }
 
-    fn is_terminated(&self) -> bool {
-        self.pc >= self.rom.len()
+    fn standard() -> Self {
+        Self::empty()
+            .with(Operation::Nop, |regs, _| regs.pc += 1)
+            .with(Operation::Acc, |regs, val| {
+                regs.acc += i64::from(val);
+                regs.pc += 1;
+            })
+            .with(Operation::Jmp, |regs, val| regs.pc += i64::from(val))
+            .with_flip(Operation::Nop, Operation::Jmp)
     }
 
-    fn is_looping(&self) -> bool {
-        self.seen_pcs.contains(&self.pc)
+    fn with(mut self, op: Operation, handler: Handler) -> Self {
+        self.specs.insert(op, OpSpec { handler, flip: None });
+        self
     }
 
-    fn step(&mut self) {
-        self.seen_pcs.insert(self.pc);
-        let inst = self.rom.get(self.pc).unwrap();
-        self.pc += 1;
-
-        match inst.op {
-            Operation::NOP => {},
-            Operation::ACC => self.acc += inst.val,
-            Operation::JMP => self.pc = (self.pc as i32 + inst.val - 1) as usize,
+    fn with_flip(mut self, a: Operation, b: Operation) -> Self {
+        for (op, other) in [(a, b), (b, a)].iter() {
+            if let Some(spec) = self.specs.get_mut(op) {
+                spec.flip = Some(*other);
+            }
         }
+        self
+    }
+
+    fn spec(&self, op: Operation) -> Option<&OpSpec> {
+        self.specs.get(&op)
+    }
+
+    // Where control goes after the instruction at `pc`. Handlers only move the pc based on their
+    // argument, never on the accumulator, so running one on scratch registers is enough.
+    fn successor(&self, inst: &Instruction, pc: usize) -> Option<i64> {
+        let mut regs = Registers {
+            pc: pc as i64,
+            acc: 0,
+        };
+        (self.spec(inst.op)?.handler)(&mut regs, inst.val);
+        Some(regs.pc)
+    }
+
+    fn flipped(&self, inst: &Instruction) -> Option<Instruction> {
+        self.spec(inst.op)?.flip.map(|op| Instruction { op, val: inst.val })
     }
 }
 
-#[derive(Clone, PartialEq)]
-enum Operation {
-    NOP,
-    ACC,
-    JMP,
+struct Console {
+    regs: Registers,
+    seen_pcs: HashSet<i64>,
+    rom: Vec<(Handler, i32)>,
+}
+
+impl Console {
+    // None if the table has no handler for one of the rom's operations
+    fn new(rom: &[Instruction], table: &InstructionTable) -> Option<Self> {
+        Some(Self {
+            regs: Registers::default(),
+            seen_pcs: HashSet::new(),
+            rom: rom
+                .iter()
+                .map(|inst| table.spec(inst.op).map(|spec| (spec.handler, inst.val)))
+                .collect::<Option<Vec<(Handler, i32)>>>()?,
+        })
+    }
+
+    fn run(&mut self) -> ExitReason {
+        loop {
+            let pc = self.regs.pc;
+            if pc == self.rom.len() as i64 {
+                return ExitReason::Terminated;
+            }
+            if pc < 0 || pc > self.rom.len() as i64 {
+                return ExitReason::OutOfBounds;
+            }
+            if !self.seen_pcs.insert(pc) {
+                return ExitReason::Looped;
+            }
+
+            let (handler, val) = self.rom[pc as usize];
+            handler(&mut self.regs, val);
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -51,71 +128,85 @@ struct Instruction {
     val: i32,
 }
 
+// Finds the single flip that lets the program terminate. Working back from the exit marks every
+// instruction that already runs off the end, then the first instruction on the original looping
+// path whose flipped target is one of those is the fix.
+fn find_patch(rom: &[Instruction], table: &InstructionTable) -> Option<usize> {
+    let exit = rom.len();
+    let mut came_from: Vec<Vec<usize>> = vec![vec![]; exit + 1];
+    for (pc, inst) in rom.iter().enumerate() {
+        let next = table.successor(inst, pc)?;
+        if next >= 0 && next <= exit as i64 {
+            came_from[next as usize].push(pc);
+        }
+    }
+
+    let mut reaches_exit = vec![false; exit + 1];
+    let mut to_visit = VecDeque::new();
+    reaches_exit[exit] = true;
+    to_visit.push_back(exit);
+    while let Some(pc) = to_visit.pop_front() {
+        for prev in came_from[pc].iter() {
+            if !reaches_exit[*prev] {
+                reaches_exit[*prev] = true;
+                to_visit.push_back(*prev);
+            }
+        }
+    }
+
+    let mut seen = vec![false; exit];
+    let mut pc = 0;
+    while pc < exit as i64 && pc >= 0 && !seen[pc as usize] {
+        let index = pc as usize;
+        seen[index] = true;
+
+        if let Some(flipped) = table.flipped(&rom[index]) {
+            let next = table.successor(&flipped, index)?;
+            if next >= 0 && next <= exit as i64 && reaches_exit[next as usize] {
+                return Some(index);
+            }
+        }
+
+        pc = table.successor(&rom[index], index)?;
+    }
+
+    None
+}
+
 #[aoc_generator(day8)]
 fn input_generator(input: &str) -> Vec<Instruction> {
-    input.lines()
+    input
+        .lines()
         .map(|line| {
             let mut line_parts = line.split_whitespace();
             let op = match line_parts.next().unwrap() {
-                "nop" => Operation::NOP,
-                "acc" => Operation::ACC,
-                "jmp" => Operation::JMP,
-                _ => panic!("Unknown operation")
+                "nop" => Operation::Nop,
+                "acc" => Operation::Acc,
+                "jmp" => Operation::Jmp,
+                op => panic!("Unknown operation: {}", op),
             };
-
             let val = line_parts.next().unwrap().parse().unwrap();
 
-            Instruction {
-                op,
-                val,
-            }
-        }).collect()
+            Instruction { op, val }
+        })
+        .collect()
 }
 
 #[aoc(day8, part1)]
-fn solve_part1(rom: &[Instruction]) -> i32 {
-    let rom = rom.to_vec();
-    let mut console = Console::new(rom);
-
-    while !console.is_looping() {
-        console.step();
-    }
-
-    console.acc
+fn solve_part1(rom: &[Instruction]) -> i64 {
+    let mut console = Console::new(rom, &InstructionTable::standard()).unwrap();
+    console.run();
+    console.regs.acc
 }
 
 #[aoc(day8, part2)]
-fn solve_part2(rom: &[Instruction]) -> i32 {
-    let original_rom = rom.to_vec();
-    let mut start_index = 0;
-
-    loop {
-        let mut rom = original_rom.clone();
-        start_index = mutate_rom(&mut rom, start_index);
-        let mut console = Console::new(rom);
-
-        while !console.is_terminated() && !console.is_looping() {
-            console.step();
-        }
-
-        if console.is_terminated() {
-            return console.acc
-        }
-    }
-}
-
-fn mutate_rom(rom: &mut Vec<Instruction>, start_index: usize) -> usize {
-    for index in start_index..rom.len() {
-        let inst = rom.get_mut(index).unwrap();
-
-        if inst.op == Operation::JMP {
-            inst.op = Operation::NOP;
-            return index + 1;
-        } else if inst.op == Operation::NOP {
-            inst.op = Operation::JMP;
-            return index + 1;
-        }
-    }
-
-    0
+fn solve_part2(rom: &[Instruction]) -> i64 {
+    let table = InstructionTable::standard();
+    let index = find_patch(rom, &table).expect("No single flip makes the program terminate");
+    let mut rom = rom.to_vec();
+    rom[index] = table.flipped(&rom[index]).unwrap();
+
+    let mut console = Console::new(&rom, &table).unwrap();
+    assert_eq!(console.run(), ExitReason::Terminated);
+    console.regs.acc
 }
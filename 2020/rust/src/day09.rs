@@ -1,7 +1,73 @@
 use std::collections::HashSet;
 
+const PREAMBLE: usize = 25;
+
+struct Xmas<'a> {
+    numbers: &'a [u64],
+    preamble: usize,
+}
+
+impl<'a> Xmas<'a> {
+    fn new(numbers: &'a [u64], preamble: usize) -> Self {
+        Self { numbers, preamble }
+    }
+
+    // Index and value of the first number that isn't the sum of two different numbers in the
+    // `preamble` numbers before it
+    fn first_invalid(&self) -> Option<(usize, u64)> {
+        let mut looking = HashSet::new();
+
+        (self.preamble..self.numbers.len())
+            .find(|i| {
+                let target = self.numbers[*i];
+                looking.clear();
+
+                !self.numbers[i - self.preamble..*i].iter().any(|num| {
+                    if looking.contains(num) && target != num * 2 {
+                        return true;
+                    }
+                    if let Some(pair) = target.checked_sub(*num) {
+                        looking.insert(pair);
+                    }
+                    false
+                })
+            })
+            .map(|i| (i, self.numbers[i]))
+    }
+
+    // Inclusive indices of a run of at least two numbers summing to the target. All numbers are
+    // positive, so the window grows on the right while short and shrinks from the left while over.
+    fn contiguous_range(&self, target: u64) -> Option<(usize, usize)> {
+        let mut start = 0;
+        let mut sum = 0;
+
+        for end in 0..self.numbers.len() {
+            sum += self.numbers[end];
+
+            while sum > target && start < end {
+                sum -= self.numbers[start];
+                start += 1;
+            }
+
+            if sum == target && end > start {
+                return Some((start, end));
+            }
+        }
+
+        None
+    }
+
+    fn weakness(&self) -> Option<(usize, usize, u64)> {
+        let (_, target) = self.first_invalid()?;
+        let (start, end) = self.contiguous_range(target)?;
+        let range = &self.numbers[start..=end];
+
+        Some((start, end, range.iter().min()? + range.iter().max()?))
+    }
+}
+
 #[aoc_generator(day9)]
-fn input_generator(input: &str) -> Vec<usize> {
+fn input_generator(input: &str) -> Vec<u64> {
     input
         .lines()
         .map(|line| line.parse().unwrap())
@@ -9,60 +75,33 @@ fn input_generator(input: &str) -> Vec<usize> {
 }
 
 #[aoc(day9, part1)]
-fn solve_part1(input: &[usize]) -> usize {
-    let mut looking = HashSet::new();
-
-    for i in 25..input.len() {
-        let target = input[i];
-        let mut found = false;
-        looking.clear();
-
-        for num in input[i - 25..i].iter() {
-            if looking.contains(num) {
-                found = true;
-                break
-            }
-            looking.insert(target - num);
-        }
-
-        if !found {
-            return target;
-        }
-    }
-
-    0
+fn solve_part1(input: &[u64]) -> u64 {
+    Xmas::new(input, PREAMBLE).first_invalid().unwrap().1
 }
 
 #[aoc(day9, part2)]
-fn solve_part2(input: &[usize]) -> usize {
-    let target = 1212510616;
-    let mut curr_nums = HashSet::new();
-
-    for start in 0..input.len() - 1 {
-        let mut remaining = target - input[start];
-        curr_nums.clear();
-        curr_nums.insert(input[start]);
-
-        for i in start + 1..input.len() {
-            if input[i] > remaining {
-                break;
-            }
+fn solve_part2(input: &[u64]) -> u64 {
+    Xmas::new(input, PREAMBLE).weakness().unwrap().2
+}
 
-            remaining -= input[i];
-            curr_nums.insert(input[i]);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            if remaining == 0 {
-                break;
-            }
-        }
+    const EXAMPLE: [u64; 20] = [
+        35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309, 576,
+    ];
 
-        if remaining == 0 {
-            break;
-        }
+    #[test]
+    fn finds_invalid_number_with_short_preamble() {
+        assert_eq!(Xmas::new(&EXAMPLE, 5).first_invalid(), Some((14, 127)));
     }
 
-    let mut nums = curr_nums.iter().collect::<Vec<&usize>>();
-    nums.sort();
+    #[test]
+    fn finds_weakness_range_with_short_preamble() {
+        let xmas = Xmas::new(&EXAMPLE, 5);
 
-    nums[0] + nums[nums.len() - 1]
+        assert_eq!(xmas.contiguous_range(127), Some((2, 5)));
+        assert_eq!(xmas.weakness(), Some((2, 5, 62)));
+    }
 }
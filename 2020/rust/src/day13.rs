@@ -1,62 +1,106 @@
+use crate::number_theory::{crt, Congruence};
+use std::fmt;
+
+// (offset in the list, bus id) for every bus in service
+fn parse_buses(line: &str) -> Vec<(usize, u64)> {
+    line.split(",")
+        .enumerate()
+        .filter(|(_, b)| b != &"x")
+        .map(|(i, b)| (i, b.parse::<u64>().unwrap()))
+        .collect()
+}
+
+// Departures from the earliest timestamp where each bus leaves its own offset after the first
+struct Schedule {
+    start: u64,
+    buses: Vec<(usize, u64)>,
+}
+
+impl Schedule {
+    fn earliest_staggered(buses: &[(usize, u64)]) -> Self {
+        let congruences = buses
+            .iter()
+            .map(|&(offset, bus)| Congruence::new(-(offset as i128), i128::from(bus)))
+            .collect::<Vec<Congruence>>();
+
+        Self {
+            start: crt(&congruences).expect("No timestamp fits every bus").residue as u64,
+            buses: buses.to_vec(),
+        }
+    }
+}
+
+// Timetable from the start to the last bus' departure, marking every bus leaving each minute
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let last_offset = self.buses.iter().map(|(offset, _)| *offset).max().unwrap_or(0);
+
+        write!(f, "time")?;
+        for (_, bus) in self.buses.iter() {
+            write!(f, "\tbus {}", bus)?;
+        }
+
+        for time in self.start..=self.start + last_offset as u64 {
+            write!(f, "\n{}", time)?;
+            for (_, bus) in self.buses.iter() {
+                write!(f, "\t{}", if time % bus == 0 { "D" } else { "." })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 #[aoc(day13, part1)]
-fn solve_part1(input: &str) -> usize {
+fn solve_part1(input: &str) -> u64 {
     let mut lines = input.lines();
-    let target = lines.next().unwrap().parse::<usize>().unwrap();
-    lines.next().unwrap()
-        .split(",")
-        .filter(|b| b != &"x")
-        .map(|b| b.parse::<usize>().unwrap())
-        .map(|b| (b, b - target % b))
+    let target = lines.next().unwrap().parse::<u64>().unwrap();
+    parse_buses(lines.next().unwrap())
+        .into_iter()
+        .map(|(_, b)| (b, (b - target % b) % b))
         .min_by(|(_, wait1), (_, wait2)| wait1.cmp(wait2))
         .map(|(b, wait)| b * wait)
         .unwrap()
 }
 
 #[aoc(day13, part2)]
-fn solve_part2(input: &str) -> usize {
-    let mut lines = input.lines();
-    lines.next();
-
-    let data = lines.next().unwrap()
-        .split(",")
-        .map(|b| b.parse::<usize>().unwrap_or(0))
-        .enumerate()
-        .filter(|&(_, b)| b != 0)
-        .map(|(i, b)| {
-            if i == 0 {
-                return (b, 0)
-            }
-            let residue = (lcm(b, i) - i) % b;
-            (b, residue)
-        })
-        .collect::<Vec<(usize, usize)>>();
+fn solve_part2(input: &str) -> u64 {
+    let buses = parse_buses(input.lines().nth(1).unwrap());
+    Schedule::earliest_staggered(&buses).start
+}
 
-    crt(&data)
+#[aoc(day13, part2, schedule)]
+fn solve_part2_schedule(input: &str) -> String {
+    let buses = parse_buses(input.lines().nth(1).unwrap());
+    Schedule::earliest_staggered(&buses).to_string()
 }
 
-fn crt(data: &[(usize, usize)]) -> usize {
-    let product = data.iter().map(|(b, _)| b).product::<usize>();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    data.iter().map(|&(b, r)| {
-        let p = product / b;
-        r * mod_inverse(p, b) * p
-    }).sum::<usize>() % product
-}
+    const EXAMPLE: &str = "939\n7,13,x,x,59,x,31,19";
 
-fn mod_inverse(x: usize, n: usize) -> usize {
-    let x = egcd(x, n).1;
-    (x % n as isize + n as isize) as usize % n
-}
+    #[test]
+    fn finds_earliest_bus_and_staggered_start() {
+        assert_eq!(solve_part1(EXAMPLE), 295);
+        assert_eq!(solve_part2(EXAMPLE), 1068781);
+    }
 
-fn lcm(a: usize, b: usize) -> usize {
-    a * b / egcd(a, b).0
-}
+    #[test]
+    fn prints_timetable_from_the_answer() {
+        let expected = [
+            "time\tbus 7\tbus 13\tbus 59\tbus 31\tbus 19",
+            "1068781\tD\t.\t.\t.\t.",
+            "1068782\t.\tD\t.\t.\t.",
+            "1068783\t.\t.\t.\t.\t.",
+            "1068784\t.\t.\t.\t.\t.",
+            "1068785\t.\t.\tD\t.\t.",
+            "1068786\t.\t.\t.\t.\t.",
+            "1068787\t.\t.\t.\tD\t.",
+            "1068788\tD\t.\t.\t.\tD",
+        ];
 
-fn egcd(a: usize, b: usize) -> (usize, isize, isize) {
-    if a == 0 {
-        (b, 0, 1)
-    } else {
-        let (g, x, y) = egcd(b % a, a);
-        (g, y - (b / a) as isize * x, x)
+        assert_eq!(solve_part2_schedule(EXAMPLE), expected.join("\n"));
     }
 }
@@ -1,57 +1,159 @@
 use std::collections::HashMap;
 
-#[aoc(day14, part1)]
-fn solve_part1(input: &str) -> u64 {
-    let mut inv_mask = 0_u64;
-    let mut base = 0_u64;
-    let mut memory: HashMap<u64, u64> = HashMap::new();
+#[derive(Clone, Copy, Default)]
+struct Mask {
+    ones: u64,
+    floating: u64,
+}
 
-    for line in input.lines() {
-        if line.starts_with("mask = ") {
-            inv_mask = 0;
-            base = 0;
-
-            for (i, c) in line[7..line.len()].chars().rev().enumerate() {
-                match c {
-                    'X' => inv_mask += 1 << i,
-                    '0' => {},
-                    '1' => base += 1 << i,
-                    _ => panic!(),
-                }
+impl Mask {
+    fn from_str(input: &str) -> Self {
+        let mut mask = Self::default();
+        for (i, c) in input.chars().rev().enumerate() {
+            match c {
+                'X' => mask.floating |= 1 << i,
+                '1' => mask.ones |= 1 << i,
+                '0' => {}
+                _ => panic!("Unknown mask bit: {}", c),
             }
-        } else {
-            let mut parts = line.split(" = ");
-            let addr_str = parts.next().unwrap();
-            let addr = addr_str[4..addr_str.len() - 1].parse::<u64>().unwrap();
+        }
+        mask
+    }
 
-            let value = parts.next().unwrap().parse::<u64>().unwrap();
-            let adj_value = (inv_mask & value) | base;
+    fn apply_to_value(&self, value: u64) -> u64 {
+        (value & self.floating) | self.ones
+    }
+
+    fn apply_to_address(&self, addr: u64) -> AddressPattern {
+        AddressPattern::new(addr | self.ones, self.floating)
+    }
+}
 
-            memory.insert(addr, adj_value);
+enum Instruction {
+    SetMask(Mask),
+    Write { addr: u64, value: u64 },
+}
+
+// Every address matching `fixed` outside the `floating` bits. Floating bits are always zero in
+// `fixed` so equal patterns compare equal.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct AddressPattern {
+    fixed: u64,
+    floating: u64,
+}
+
+impl AddressPattern {
+    fn new(fixed: u64, floating: u64) -> Self {
+        Self {
+            fixed: fixed & !floating,
+            floating,
         }
     }
 
-    memory.values().sum()
+    fn len(&self) -> u64 {
+        1 << self.floating.count_ones()
+    }
+
+    fn intersection(&self, other: &Self) -> Option<Self> {
+        let both_fixed = !self.floating & !other.floating;
+        if (self.fixed ^ other.fixed) & both_fixed != 0 {
+            return None;
+        }
+
+        Some(Self::new(
+            self.fixed | other.fixed,
+            self.floating & other.floating,
+        ))
+    }
+
+    // Disjoint patterns covering every address in self that isn't in other. Each bit that floats
+    // here but is pinned in the overlap splits off the half that disagrees with the overlap, then
+    // gets pinned to the overlap's value before moving on to the next bit.
+    fn subtract(&self, other: &Self) -> Vec<Self> {
+        let overlap = match self.intersection(other) {
+            Some(overlap) => overlap,
+            None => return vec![*self],
+        };
+
+        let mut pieces = vec![];
+        let mut remaining = *self;
+        let mut split_bits = self.floating & !overlap.floating;
+        while split_bits != 0 {
+            let bit = split_bits & split_bits.wrapping_neg();
+            split_bits &= !bit;
+
+            let floating = remaining.floating & !bit;
+            pieces.push(Self::new(
+                remaining.fixed | (!overlap.fixed & bit),
+                floating,
+            ));
+            remaining = Self::new(remaining.fixed | (overlap.fixed & bit), floating);
+        }
+
+        pieces
+    }
 }
 
-#[aoc(day14, part2)]
-fn solve_part2(input: &str) -> u64 {
-    let mut mask = String::new();
-    let mut memory: HashMap<u64, u64> = HashMap::new();
+// Memory as disjoint address patterns, each holding one value. A write carves its pattern out of
+// every existing region, so each address is only ever counted with its latest value.
+#[derive(Default)]
+struct FloatingMemory {
+    regions: Vec<(AddressPattern, u64)>,
+}
+
+impl FloatingMemory {
+    fn write(&mut self, pattern: AddressPattern, value: u64) {
+        self.regions = self
+            .regions
+            .iter()
+            .flat_map(|(region, old_value)| {
+                region
+                    .subtract(&pattern)
+                    .into_iter()
+                    .map(move |piece| (piece, *old_value))
+            })
+            .collect();
+        self.regions.push((pattern, value));
+    }
+
+    // A region can span all 2^36 addresses, so the total needs more than 64 bits
+    fn sum(&self) -> u128 {
+        self.regions
+            .iter()
+            .map(|(region, value)| u128::from(region.len()) * u128::from(*value))
+            .sum()
+    }
+}
+
+#[aoc_generator(day14)]
+fn input_generator(input: &str) -> Vec<Instruction> {
+    input
+        .lines()
+        .map(|line| {
+            if let Some(mask) = line.strip_prefix("mask = ") {
+                return Instruction::SetMask(Mask::from_str(mask));
+            }
 
-    for line in input.lines() {
-        if line.starts_with("mask = ") {
-            mask = line[7..line.len()].to_string();
-        } else {
             let mut parts = line.split(" = ");
             let addr_str = parts.next().unwrap();
             let addr = addr_str[4..addr_str.len() - 1].parse::<u64>().unwrap();
-            let mask_addresses = build_mask_addresses(&mask, addr);
-
             let value = parts.next().unwrap().parse::<u64>().unwrap();
 
-            for mask_addr in mask_addresses {
-                memory.insert(mask_addr, value);
+            Instruction::Write { addr, value }
+        })
+        .collect()
+}
+
+#[aoc(day14, part1)]
+fn solve_part1(program: &[Instruction]) -> u64 {
+    let mut mask = Mask::default();
+    let mut memory: HashMap<u64, u64> = HashMap::new();
+
+    for instruction in program {
+        match instruction {
+            Instruction::SetMask(new_mask) => mask = *new_mask,
+            Instruction::Write { addr, value } => {
+                memory.insert(*addr, mask.apply_to_value(*value));
             }
         }
     }
@@ -59,28 +161,19 @@ fn solve_part2(input: &str) -> u64 {
     memory.values().sum()
 }
 
-fn build_mask_addresses(mask: &str, addr: u64) -> Vec<u64> {
-    let mut floating_bits = vec![];
-    let mut base_addr = 0_u64;
+#[aoc(day14, part2)]
+fn solve_part2(program: &[Instruction]) -> u128 {
+    let mut mask = Mask::default();
+    let mut memory = FloatingMemory::default();
 
-    for (i, c) in mask.chars().rev().enumerate() {
-        match c {
-            'X' => floating_bits.push(i),
-            '1' => base_addr += 1 << i,
-            '0' => if (addr >> i) & 1 > 0 { base_addr += 1 << i },
-            _ => panic!(),
+    for instruction in program {
+        match instruction {
+            Instruction::SetMask(new_mask) => mask = *new_mask,
+            Instruction::Write { addr, value } => {
+                memory.write(mask.apply_to_address(*addr), *value);
+            }
         }
     }
 
-    (0..2_usize.pow(floating_bits.len() as u32)).map(|addr_ind| {
-        let mut addr = base_addr.clone();
-        for (i, c) in format!("{:b}", addr_ind).chars().rev().enumerate() {
-            if c == '0' {
-                continue
-            }
-
-            addr += 1 << floating_bits[i]
-        }
-        addr
-    }).collect()
+    memory.sum()
 }
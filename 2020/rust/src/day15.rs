@@ -1,18 +1,100 @@
 use std::collections::HashMap;
 
+// Van Eck style sequence yielding every number spoken, starting numbers included, up to the turn
+// limit. No number spoken can be larger than the turn count or the largest starting number, so
+// the last-seen table is allocated up front and indexed by number, with 0 meaning never spoken.
+struct MemoryGame {
+    starting: Vec<u32>,
+    last_seen: Vec<u32>,
+    turn: u32,
+    turn_limit: u32,
+    last_spoken: u32,
+}
+
+impl MemoryGame {
+    fn new(starting: &[u32], turn_limit: u32) -> Self {
+        let max_start = starting.iter().max().cloned().unwrap_or(0);
+        let size = turn_limit.max(max_start + 1) as usize;
+
+        Self {
+            starting: starting.to_vec(),
+            last_seen: vec![0; size],
+            turn: 0,
+            turn_limit,
+            last_spoken: 0,
+        }
+    }
+
+    // Turn each number was last spoken on, indexed by number. The most recent number is only
+    // recorded once the next one has been spoken.
+    fn last_seen(&self) -> &[u32] {
+        &self.last_seen
+    }
+}
+
+impl Iterator for MemoryGame {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        if self.turn >= self.turn_limit {
+            return None;
+        }
+        self.turn += 1;
+
+        let spoken = match self.starting.get(self.turn as usize - 1) {
+            Some(num) => *num,
+            None => match self.last_seen()[self.last_spoken as usize] {
+                0 => 0,
+                seen => self.turn - 1 - seen,
+            },
+        };
+
+        if self.turn > 1 {
+            self.last_seen[self.last_spoken as usize] = self.turn - 1;
+        }
+        self.last_spoken = spoken;
+        Some(spoken)
+    }
+}
+
+fn parse_starting(input: &str) -> Vec<u32> {
+    input
+        .trim()
+        .split(",")
+        .map(|n| n.parse::<u32>().unwrap())
+        .collect()
+}
+
+fn play(input: &str, turn_limit: u32) -> u32 {
+    MemoryGame::new(&parse_starting(input), turn_limit)
+        .last()
+        .unwrap()
+}
+
 #[aoc(day15, part1)]
-fn solve_part1(input: &str) -> usize {
-    run(input, 2020)
+fn solve_part1(input: &str) -> u32 {
+    play(input, 2020)
 }
 
 #[aoc(day15, part2)]
-fn solve_part2(input: &str) -> usize {
-    run(input, 30_000_000)
+fn solve_part2(input: &str) -> u32 {
+    play(input, 30_000_000)
+}
+
+// The original HashMap-backed game, kept so `cargo aoc bench` can compare it with the table
+#[aoc(day15, part1, hashmap)]
+fn solve_part1_hashmap(input: &str) -> usize {
+    run_hashmap(input, 2020)
+}
+
+#[aoc(day15, part2, hashmap)]
+fn solve_part2_hashmap(input: &str) -> usize {
+    run_hashmap(input, 30_000_000)
 }
 
-fn run(input: &str, turn_limit: usize) -> usize {
+fn run_hashmap(input: &str, turn_limit: usize) -> usize {
     let mut turn_spoken: HashMap<usize, usize> = HashMap::new();
-    let start_nums = input.split(",")
+    let start_nums = input.trim().split(",")
         .map(|n| n.parse::<usize>().unwrap())
         .collect::<Vec<usize>>();
 
@@ -33,3 +115,23 @@ fn run(input: &str, turn_limit: usize) -> usize {
 
     last_spoken
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streams_the_example_sequence() {
+        let spoken = MemoryGame::new(&[0, 3, 6], 10).collect::<Vec<u32>>();
+
+        assert_eq!(spoken, vec![0, 3, 6, 0, 3, 3, 1, 0, 4, 0]);
+    }
+
+    #[test]
+    fn last_seen_table_tracks_turns_by_number() {
+        let mut game = MemoryGame::new(&[0, 3, 6], 10);
+        game.by_ref().for_each(drop);
+
+        assert_eq!(game.last_seen()[..7], [8, 7, 0, 6, 9, 0, 3]);
+    }
+}
@@ -1,64 +1,212 @@
-#[aoc(day18, part1)]
-fn solve_part1(input: &str) -> usize {
-    input.lines().map(|line| solve(line.replace(" ", ""), false)).sum()
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Num(u64),
+    Op(char),
+    Open,
+    Close,
 }
 
-#[aoc(day18, part2)]
-fn solve_part2(input: &str) -> usize {
-    input.lines().map(|line| solve(line.replace(" ", ""), true)).sum()
+#[derive(Debug)]
+enum ExprError {
+    UnknownChar { pos: usize, c: char },
+    UnexpectedToken { pos: usize },
+    UnexpectedEnd,
+    UnclosedParen { pos: usize },
+    Overflow { pos: usize },
+    SumOverflow { line: usize },
 }
 
-fn solve(expression: String, part2: bool) -> usize {
-    if expression.contains("(") {
-        let index = expression.find("(").unwrap();
-        let mut b_count = 0;
-        for (i, c) in expression.chars().enumerate().skip(index) {
-            if c == ')' {
-                if b_count == 1 {
-                    let inner_value = solve(expression[index + 1..i].to_string(), part2);
-                    let subbed = format!("{}{}{}", &expression[0..index], inner_value, &expression[i + 1..expression.len()]);
-                    return solve(subbed, part2)
-                }
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExprError::UnknownChar { pos, c } => write!(f, "Unknown char {:?} at {}", c, pos),
+            ExprError::UnexpectedToken { pos } => write!(f, "Unexpected token at {}", pos),
+            ExprError::UnexpectedEnd => write!(f, "Unexpected end of expression"),
+            ExprError::UnclosedParen { pos } => write!(f, "Paren at {} is never closed", pos),
+            ExprError::Overflow { pos } => write!(f, "Operation at {} overflows", pos),
+            ExprError::SumOverflow { line } => write!(f, "Adding line {} overflows the sum", line),
+        }
+    }
+}
+
+// Tokens paired with the char offset they start at. Any char with an entry in the precedence
+// table is an operator.
+fn tokenize(input: &str, precedence: &Precedence) -> Result<Vec<(usize, Token)>, ExprError> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().peekable();
 
-                b_count -= 1;
-            } else if c == '(' {
-                b_count += 1;
+    while let Some((pos, c)) = chars.next() {
+        let token = match c {
+            ' ' => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            _ if precedence.operators.contains_key(&c) => Token::Op(c),
+            '0'..='9' => {
+                let mut val = u64::from(c.to_digit(10).unwrap());
+                while let Some(digit) = chars.peek().and_then(|(_, d)| d.to_digit(10)) {
+                    val = val
+                        .checked_mul(10)
+                        .and_then(|v| v.checked_add(u64::from(digit)))
+                        .ok_or(ExprError::Overflow { pos })?;
+                    chars.next();
+                }
+                Token::Num(val)
             }
+            _ => return Err(ExprError::UnknownChar { pos, c }),
+        };
+        tokens.push((pos, token));
+    }
+
+    Ok(tokens)
+}
+
+// Applies an operator, None on overflow or any other invalid result
+type Apply = fn(u64, u64) -> Option<u64>;
+
+// Binding power and evaluation of each operator, higher levels bind tighter. Operators on the
+// same level are left associative.
+struct Precedence {
+    operators: HashMap<char, (u8, Apply)>,
+}
+
+impl Precedence {
+    fn empty() -> Self {
+        Self {
+            operators: HashMap::new(),
         }
     }
 
-    if part2 && expression.contains("+") && expression.contains("*") {
-        let index = expression.find("*").unwrap();
-        let left_value = solve(expression[0..index].to_string(), part2);
-        let right_value = solve(expression[index + 1..expression.len()].to_string(), part2);
-        return left_value * right_value;
+    fn with(mut self, op: char, level: u8, apply: Apply) -> Self {
+        self.operators.insert(op, (level, apply));
+        self
     }
+}
 
-    // no parens
-    let mut total = 0_usize;
-    let mut val = 0_usize;
-    let mut is_add = true;
-    for c in expression.chars() {
-        match c {
-            '0'..='9' => val = (val * 10) + (c as usize - '0' as usize),
-            '+' | '*' => {
-                if is_add {
-                    total += val;
-                } else {
-                    total *= val;
-                }
-                val = 0;
-                is_add = c == '+';
-            },
-            _ => panic!("Unknown char")
+#[derive(Debug)]
+enum Expr {
+    Num(u64),
+    BinOp {
+        apply: Apply,
+        pos: usize,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+}
+
+impl Expr {
+    fn eval(&self) -> Result<u64, ExprError> {
+        match self {
+            Expr::Num(val) => Ok(*val),
+            Expr::BinOp {
+                apply,
+                pos,
+                lhs,
+                rhs,
+            } => apply(lhs.eval()?, rhs.eval()?).ok_or(ExprError::Overflow { pos: *pos }),
+        }
+    }
+}
+
+// Pratt parser over the token stream
+struct Parser<'a> {
+    tokens: Vec<(usize, Token)>,
+    index: usize,
+    precedence: &'a Precedence,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(input: &str, precedence: &'a Precedence) -> Result<Expr, ExprError> {
+        let mut parser = Self {
+            tokens: tokenize(input, precedence)?,
+            index: 0,
+            precedence,
+        };
+
+        let expr = parser.expression(0)?;
+        match parser.peek() {
+            Some((pos, _)) => Err(ExprError::UnexpectedToken { pos }),
+            None => Ok(expr),
+        }
+    }
+
+    fn peek(&self) -> Option<(usize, Token)> {
+        self.tokens.get(self.index).cloned()
+    }
+
+    fn next(&mut self) -> Option<(usize, Token)> {
+        let token = self.peek();
+        self.index += 1;
+        token
+    }
+
+    fn expression(&mut self, min_level: u8) -> Result<Expr, ExprError> {
+        let mut lhs = self.operand()?;
+
+        while let Some((pos, Token::Op(op))) = self.peek() {
+            let (level, apply) = self.precedence.operators[&op];
+            if level < min_level {
+                break;
+            }
+
+            self.next();
+            let rhs = self.expression(level + 1)?;
+            lhs = Expr::BinOp {
+                apply,
+                pos,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
         }
+
+        Ok(lhs)
     }
 
-    if is_add {
-        total += val;
-    } else {
-        total *= val;
+    fn operand(&mut self) -> Result<Expr, ExprError> {
+        match self.next() {
+            Some((_, Token::Num(val))) => Ok(Expr::Num(val)),
+            Some((pos, Token::Open)) => {
+                let expr = self.expression(0)?;
+                match self.next() {
+                    Some((_, Token::Close)) => Ok(expr),
+                    _ => Err(ExprError::UnclosedParen { pos }),
+                }
+            }
+            Some((pos, _)) => Err(ExprError::UnexpectedToken { pos }),
+            None => Err(ExprError::UnexpectedEnd),
+        }
     }
+}
 
-    total
+fn sum_expressions(input: &str, precedence: &Precedence) -> u64 {
+    input
+        .lines()
+        .enumerate()
+        .try_fold(0_u64, |total, (index, line)| {
+            let value = Parser::parse(line, precedence)
+                .and_then(|expr| expr.eval())
+                .unwrap_or_else(|error| panic!("{} in {:?}", error, line));
+            total
+                .checked_add(value)
+                .ok_or(ExprError::SumOverflow { line: index + 1 })
+        })
+        .unwrap_or_else(|error| panic!("{}", error))
+}
+
+#[aoc(day18, part1)]
+fn solve_part1(input: &str) -> u64 {
+    let precedence = Precedence::empty()
+        .with('+', 1, u64::checked_add)
+        .with('*', 1, u64::checked_mul);
+    sum_expressions(input, &precedence)
+}
+
+#[aoc(day18, part2)]
+fn solve_part2(input: &str) -> u64 {
+    let precedence = Precedence::empty()
+        .with('+', 2, u64::checked_add)
+        .with('*', 1, u64::checked_mul);
+    sum_expressions(input, &precedence)
 }
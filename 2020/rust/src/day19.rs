@@ -1,89 +1,117 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
 enum Rule {
-    Str(String),
-    Or(Box<Rule>, Box<Rule>),
+    Char(char),
+    // Each alternative is a sequence of rule ids that must match one after another
+    Alts(Vec<Vec<usize>>),
 }
 
 impl Rule {
-    fn and(&self, other: &Self) -> Self {
-        match self {
-            Self::Str(a) => {
-                match other {
-                    Self::Str(b) => Self::Str(format!("{}{}", a, b)),
-                    Self::Or(b1, b2) => Self::Or(Box::from(self.and(b1)), Box::from(self.and(b2))),
-                }
-            },
-            Self::Or(a1, a2) => Self::Or(Box::from(a1.and(other)), Box::from(a2.and(other))),
+    fn from_str(input: &str) -> Self {
+        let input = input.trim();
+        if input.starts_with('"') {
+            return Self::Char(input.chars().nth(1).unwrap());
         }
-    }
 
-    fn unwind(self) -> Vec<String> {
-        match self {
-            Self::Str(s) => vec![s],
-            Self::Or(r1, r2) => r1.unwind().into_iter().chain(r2.unwind().into_iter()).collect(),
-        }
+        Self::Alts(
+            input
+                .split(" | ")
+                .map(|seq| {
+                    seq.split_whitespace()
+                        .map(|id| id.parse::<usize>().unwrap())
+                        .collect()
+                })
+                .collect(),
+        )
     }
 }
 
-impl ToString for Rule {
-    fn to_string(&self) -> String {
-        match self {
-            Self::Str(s) => s.clone(),
-            Self::Or(r1, r2) => format!("({} | {})", r1.to_string(), r2.to_string()),
+struct Grammar {
+    rules: HashMap<usize, Rule>,
+}
+
+impl Grammar {
+    fn from_str(input: &str) -> Self {
+        let mut grammar = Self {
+            rules: HashMap::new(),
+        };
+        for line in input.lines() {
+            grammar = grammar.with_rule(line);
         }
+        grammar
     }
-}
 
-#[aoc(day19, part1)]
-fn solve_part1(input: &str) -> usize {
-    let mut input_parts = input.split("\n\n");
-    let rule_0 = parse_and_resolve_rules(input_parts.next().unwrap());
-    let possible_values = rule_0.unwind().into_iter().collect::<HashSet<String>>();
+    // Adds or replaces a rule given as `id: definition`
+    fn with_rule(mut self, line: &str) -> Self {
+        let mut parts = line.split(": ");
+        let id = parts.next().unwrap().parse::<usize>().unwrap();
+        self.rules.insert(id, Rule::from_str(parts.next().unwrap()));
+        self
+    }
 
-    input_parts.next().unwrap().lines()
-        .filter(|line| possible_values.contains(&line.to_string()))
-        .count()
-}
+    fn matches(&self, message: &str) -> bool {
+        let chars = message.chars().collect::<Vec<char>>();
+        self.match_rule(0, &chars, 0).contains(&chars.len())
+    }
 
-fn parse_and_resolve_rules(input: &str) -> Rule {
-    let raw_rules = input.lines()
-        .map(|line| {
-            let mut parts = line.split(": ");
-            let rule_id = parts.next().unwrap().parse::<usize>().unwrap();
-            let rule_str = parts.next().unwrap().to_string();
+    // Every position a match of the rule starting at `pos` could end at. Keeping all of them
+    // rather than the first lets a sequence backtrack into a shorter or longer match of an
+    // earlier rule. Every rule consumes at least one character, so recursive rules like
+    // `8: 42 | 42 8` stop once the message runs out.
+    fn match_rule(&self, id: usize, chars: &[char], pos: usize) -> Vec<usize> {
+        if pos >= chars.len() {
+            return vec![];
+        }
 
-            (rule_id, rule_str)
-        }).collect::<HashMap<usize, String>>();
+        match &self.rules[&id] {
+            Rule::Char(c) => {
+                if chars[pos] == *c {
+                    vec![pos + 1]
+                } else {
+                    vec![]
+                }
+            }
+            Rule::Alts(alts) => alts
+                .iter()
+                .flat_map(|seq| self.match_seq(seq, chars, pos))
+                .collect(),
+        }
+    }
 
-    let mut rules: HashMap<usize, Rule> = HashMap::new();
-    resolve_rule(raw_rules.get(&0).unwrap(), &mut rules, &raw_rules)
+    fn match_seq(&self, seq: &[usize], chars: &[char], pos: usize) -> Vec<usize> {
+        seq.iter().fold(vec![pos], |ends, id| {
+            let mut next_ends = ends
+                .into_iter()
+                .flat_map(|end| self.match_rule(*id, chars, end))
+                .collect::<Vec<usize>>();
+            next_ends.sort_unstable();
+            next_ends.dedup();
+            next_ends
+        })
+    }
 }
 
-fn resolve_rule(rule_str: &str, rules: &mut HashMap<usize, Rule>, raw_rules: &HashMap<usize, String>) -> Rule {
-    if rule_str.contains(" | ") {
-        let mut rule_parts = rule_str.split(" | ");
-        let first_rule = resolve_rule(rule_parts.next().unwrap(), rules, raw_rules);
-        let second_rule = resolve_rule(rule_parts.next().unwrap(), rules, raw_rules);
-        return Rule::Or(Box::from(first_rule), Box::from(second_rule));
-    }
+fn count_matches(grammar: &Grammar, messages: &str) -> usize {
+    messages
+        .lines()
+        .filter(|line| grammar.matches(line))
+        .count()
+}
 
-    let mut rule = Rule::Str(String::new());
-    for token in rule_str.split_whitespace() {
-        if token.contains("\"") {
-            rule = rule.and(&Rule::Str(String::from(&token[1..2])));
-        } else {
-            let nested_rule_id = token.parse::<usize>().unwrap();
+#[aoc(day19, part1)]
+fn solve_part1(input: &str) -> usize {
+    let mut input_parts = input.split("\n\n");
+    let grammar = Grammar::from_str(input_parts.next().unwrap());
 
-            if !rules.contains_key(&nested_rule_id) {
-                let nested_raw_rule = raw_rules.get(&nested_rule_id).unwrap();
-                let nested_rule = resolve_rule(nested_raw_rule, rules, raw_rules);
-                rules.insert(nested_rule_id, nested_rule);
-            }
-            let nested_rule = rules.get(&nested_rule_id).unwrap();
-            rule = rule.and(nested_rule);
-        }
-    }
+    count_matches(&grammar, input_parts.next().unwrap())
+}
+
+#[aoc(day19, part2)]
+fn solve_part2(input: &str) -> usize {
+    let mut input_parts = input.split("\n\n");
+    let grammar = Grammar::from_str(input_parts.next().unwrap())
+        .with_rule("8: 42 | 42 8")
+        .with_rule("11: 42 31 | 42 11 31");
 
-    rule
+    count_matches(&grammar, input_parts.next().unwrap())
 }
@@ -1,89 +1,245 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 
-enum Rule {
-    Str(String),
-    Or(Box<Rule>, Box<Rule>),
-}
+const SEA_MONSTER: [&str; 3] = [
+    "                  # ",
+    "#    ##    #    ##  ",
+    " #  #    #   #      ",
+];
 
-impl Rule {
-    fn and(&self, other: &Self) -> Self {
-        match self {
-            Self::Str(a) => {
-                match other {
-                    Self::Str(b) => Self::Str(format!("{}{}", a, b)),
-                    Self::Or(b1, b2) => Self::Or(Box::from(self.and(b1)), Box::from(self.and(b2))),
-                }
-            },
-            Self::Or(a1, a2) => Self::Or(Box::from(a1.and(other)), Box::from(a2.and(other))),
-        }
-    }
+type Image = Vec<Vec<bool>>;
 
-    fn unwind(self) -> Vec<String> {
-        match self {
-            Self::Str(s) => vec![s],
-            Self::Or(r1, r2) => r1.unwind().into_iter().chain(r2.unwind().into_iter()).collect(),
-        }
-    }
+fn rotate(image: &Image) -> Image {
+    let size = image.len();
+    (0..size)
+        .map(|row| (0..size).map(|col| image[size - 1 - col][row]).collect())
+        .collect()
+}
+
+fn flip(image: &Image) -> Image {
+    image
+        .iter()
+        .map(|row| row.iter().rev().cloned().collect())
+        .collect()
 }
 
-impl ToString for Rule {
-    fn to_string(&self) -> String {
-        match self {
-            Self::Str(s) => s.clone(),
-            Self::Or(r1, r2) => format!("({} | {})", r1.to_string(), r2.to_string()),
+// The four rotations of the image followed by the four rotations of its mirror
+fn orientations(image: &Image) -> Vec<Image> {
+    let mut all = vec![];
+    let mut current = image.clone();
+    for _ in 0..2 {
+        for _ in 0..4 {
+            let next = rotate(&current);
+            all.push(current);
+            current = next;
         }
+        current = flip(&current);
     }
+    all
 }
 
-#[aoc(day19, part1)]
-fn solve_part1(input: &str) -> usize {
-    let mut input_parts = input.split("\n\n");
-    let rule_0 = parse_and_resolve_rules(input_parts.next().unwrap());
-    let possible_values = rule_0.unwind().into_iter().collect::<HashSet<String>>();
+struct Tile {
+    id: u64,
+    pixels: Image,
+}
 
-    input_parts.next().unwrap().lines()
-        .filter(|line| possible_values.contains(&line.to_string()))
-        .count()
+struct Placement {
+    tile: usize,
+    pixels: Image,
 }
 
-fn parse_and_resolve_rules(input: &str) -> Rule {
-    let raw_rules = input.lines()
-        .map(|line| {
-            let mut parts = line.split(": ");
-            let rule_id = parts.next().unwrap().parse::<usize>().unwrap();
-            let rule_str = parts.next().unwrap().to_string();
+impl Placement {
+    fn top(&self) -> &[bool] {
+        &self.pixels[0]
+    }
 
-            (rule_id, rule_str)
-        }).collect::<HashMap<usize, String>>();
+    fn bottom(&self) -> &[bool] {
+        &self.pixels[self.pixels.len() - 1]
+    }
 
-    let mut rules: HashMap<usize, Rule> = HashMap::new();
-    resolve_rule(raw_rules.get(&0).unwrap(), &mut rules, &raw_rules)
+    fn left(&self) -> Vec<bool> {
+        self.pixels.iter().map(|row| row[0]).collect()
+    }
+
+    fn right(&self) -> Vec<bool> {
+        self.pixels.iter().map(|row| row[row.len() - 1]).collect()
+    }
 }
 
-fn resolve_rule(rule_str: &str, rules: &mut HashMap<usize, Rule>, raw_rules: &HashMap<usize, String>) -> Rule {
-    if rule_str.contains(" | ") {
-        let mut rule_parts = rule_str.split(" | ");
-        let first_rule = resolve_rule(rule_parts.next().unwrap(), rules, raw_rules);
-        let second_rule = resolve_rule(rule_parts.next().unwrap(), rules, raw_rules);
-        return Rule::Or(Box::from(first_rule), Box::from(second_rule));
+struct Jigsaw<'a> {
+    tiles: &'a [Tile],
+    size: usize,
+    // Every orientation of every tile, indexed by tile
+    options: Vec<Vec<Placement>>,
+}
+
+impl<'a> Jigsaw<'a> {
+    fn new(tiles: &'a [Tile]) -> Self {
+        let size = (tiles.len() as f64).sqrt() as usize;
+        assert_eq!(size * size, tiles.len(), "Tiles don't form a square");
+
+        let options = tiles
+            .iter()
+            .enumerate()
+            .map(|(tile, t)| {
+                orientations(&t.pixels)
+                    .into_iter()
+                    .map(|pixels| Placement { tile, pixels })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            tiles,
+            size,
+            options,
+        }
     }
 
-    let mut rule = Rule::Str(String::new());
-    for token in rule_str.split_whitespace() {
-        if token.contains("\"") {
-            rule = rule.and(&Rule::Str(String::from(&token[1..2])));
+    // Placements in row-major order, each referencing an entry of `options`
+    fn assemble(&self) -> Option<Vec<&Placement>> {
+        let mut placed = vec![];
+        let mut used = vec![false; self.tiles.len()];
+        if self.place_next(&mut placed, &mut used) {
+            Some(placed)
         } else {
-            let nested_rule_id = token.parse::<usize>().unwrap();
+            None
+        }
+    }
+
+    fn place_next<'b>(&'b self, placed: &mut Vec<&'b Placement>, used: &mut [bool]) -> bool {
+        let position = placed.len();
+        if position == self.tiles.len() {
+            return true;
+        }
+
+        let (row, col) = (position / self.size, position % self.size);
+        for tile in 0..self.tiles.len() {
+            if used[tile] {
+                continue;
+            }
 
-            if !rules.contains_key(&nested_rule_id) {
-                let nested_raw_rule = raw_rules.get(&nested_rule_id).unwrap();
-                let nested_rule = resolve_rule(nested_raw_rule, rules, raw_rules);
-                rules.insert(nested_rule_id, nested_rule);
+            for option in self.options[tile].iter() {
+                if col > 0 && placed[position - 1].right() != option.left() {
+                    continue;
+                }
+                if row > 0 && placed[position - self.size].bottom() != option.top() {
+                    continue;
+                }
+
+                placed.push(option);
+                used[tile] = true;
+                if self.place_next(placed, used) {
+                    return true;
+                }
+                used[tile] = false;
+                placed.pop();
             }
-            let nested_rule = rules.get(&nested_rule_id).unwrap();
-            rule = rule.and(nested_rule);
+        }
+
+        false
+    }
+
+    fn corner_product(&self, placed: &[&Placement]) -> u64 {
+        let last = self.size - 1;
+        [0, last, last * self.size, last * self.size + last]
+            .iter()
+            .map(|index| self.tiles[placed[*index].tile].id)
+            .product()
+    }
+
+    // Joins the placed tiles into one image with every tile's border removed
+    fn image(&self, placed: &[&Placement]) -> Image {
+        let inner = placed[0].pixels.len() - 2;
+        let mut image = vec![vec![]; self.size * inner];
+
+        for (position, placement) in placed.iter().enumerate() {
+            let row = position / self.size;
+            for (offset, pixels) in placement.pixels[1..=inner].iter().enumerate() {
+                image[row * inner + offset].extend_from_slice(&pixels[1..=inner]);
+            }
+        }
+
+        image
+    }
+}
+
+fn sea_monster_offsets() -> Vec<(usize, usize)> {
+    SEA_MONSTER
+        .iter()
+        .enumerate()
+        .flat_map(|(row, line)| {
+            line.chars()
+                .enumerate()
+                .filter(|(_, c)| *c == '#')
+                .map(move |(col, _)| (row, col))
+        })
+        .collect()
+}
+
+// Cells covered by sea monsters in the first orientation that contains any. Monsters may overlap,
+// so covered cells are collected into a set rather than counted per monster.
+fn find_sea_monsters(image: &Image) -> HashSet<(usize, usize)> {
+    let offsets = sea_monster_offsets();
+    let height = SEA_MONSTER.len();
+    let width = SEA_MONSTER[0].len();
+
+    for oriented in orientations(image) {
+        let mut covered = HashSet::new();
+        for row in 0..=(oriented.len() - height) {
+            for col in 0..=(oriented[row].len() - width) {
+                if offsets
+                    .iter()
+                    .all(|(dr, dc)| oriented[row + dr][col + dc])
+                {
+                    covered.extend(offsets.iter().map(|(dr, dc)| (row + dr, col + dc)));
+                }
+            }
+        }
+
+        if !covered.is_empty() {
+            return covered;
         }
     }
 
-    rule
+    HashSet::new()
+}
+
+#[aoc_generator(day20)]
+fn input_generator(input: &str) -> Vec<Tile> {
+    input
+        .split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .map(|block| {
+            let mut lines = block.lines();
+            let id = lines
+                .next()
+                .unwrap()
+                .trim_start_matches("Tile ")
+                .trim_end_matches(':')
+                .parse()
+                .unwrap();
+            let pixels = lines
+                .map(|line| line.chars().map(|c| c == '#').collect())
+                .collect();
+
+            Tile { id, pixels }
+        })
+        .collect()
+}
+
+#[aoc(day20, part1)]
+fn solve_part1(tiles: &[Tile]) -> u64 {
+    let jigsaw = Jigsaw::new(tiles);
+    let placed = jigsaw.assemble().expect("Tiles can't be assembled");
+    jigsaw.corner_product(&placed)
+}
+
+#[aoc(day20, part2)]
+fn solve_part2(tiles: &[Tile]) -> usize {
+    let jigsaw = Jigsaw::new(tiles);
+    let placed = jigsaw.assemble().expect("Tiles can't be assembled");
+    let image = jigsaw.image(&placed);
+
+    let rough = image.iter().flatten().filter(|pixel| **pixel).count();
+    rough - find_sea_monsters(&image).len()
 }
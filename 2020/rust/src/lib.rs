@@ -20,5 +20,8 @@ mod day16;
 mod day17;
 mod day18;
 mod day19;
+mod day20;
+
+mod number_theory;
 
 aoc_lib!{ year = 2020 }
@@ -0,0 +1,117 @@
+// Shared modular arithmetic. Everything works in i128 so moduli up to 2^63 can be multiplied
+// together without overflowing.
+
+// (g, x, y) with a * x + b * y == g == gcd(a, b), and g never negative
+pub fn egcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        if a < 0 {
+            (-a, -1, 0)
+        } else {
+            (a, 1, 0)
+        }
+    } else {
+        let (g, x, y) = egcd(b, a.rem_euclid(b));
+        (g, y, x - a.div_euclid(b) * y)
+    }
+}
+
+pub fn gcd(a: i128, b: i128) -> i128 {
+    egcd(a, b).0
+}
+
+// x in 0..modulus with a * x == 1 (mod modulus), if a and modulus are coprime
+pub fn mod_inverse(a: i128, modulus: i128) -> Option<i128> {
+    let (g, x, _) = egcd(a.rem_euclid(modulus), modulus);
+    if g == 1 {
+        Some(x.rem_euclid(modulus))
+    } else {
+        None
+    }
+}
+
+// x == residue (mod modulus), with the residue kept in 0..modulus
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Congruence {
+    pub residue: i128,
+    pub modulus: i128,
+}
+
+impl Congruence {
+    pub fn new(residue: i128, modulus: i128) -> Self {
+        Self {
+            residue: residue.rem_euclid(modulus),
+            modulus,
+        }
+    }
+
+    // The single congruence satisfied by exactly the numbers satisfying both, over the lcm of the
+    // two moduli. Moduli don't need to be coprime, but then the residues have to agree modulo
+    // their gcd or there is no solution.
+    pub fn merge(&self, other: &Self) -> Option<Self> {
+        let g = gcd(self.modulus, other.modulus);
+        let diff = other.residue - self.residue;
+        if diff % g != 0 {
+            return None;
+        }
+
+        let other_step = other.modulus / g;
+        let inverse = mod_inverse(self.modulus / g, other_step)?;
+        let k = (diff / g).rem_euclid(other_step) * inverse % other_step;
+
+        let modulus = self.modulus * other_step;
+        Some(Self::new(self.residue + self.modulus * k, modulus))
+    }
+}
+
+// Generalised Chinese Remainder Theorem: the combined congruence, or None if they contradict
+pub fn crt(congruences: &[Congruence]) -> Option<Congruence> {
+    congruences
+        .iter()
+        .try_fold(Congruence::new(0, 1), |combined, congruence| {
+            combined.merge(congruence)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_non_coprime_moduli() {
+        let merged = crt(&[Congruence::new(2, 6), Congruence::new(4, 8)]);
+
+        assert_eq!(merged, Some(Congruence::new(20, 24)));
+    }
+
+    #[test]
+    fn reports_contradicting_congruences() {
+        assert_eq!(Congruence::new(1, 6).merge(&Congruence::new(2, 8)), None);
+        assert_eq!(crt(&[Congruence::new(1, 6), Congruence::new(2, 8)]), None);
+    }
+
+    #[test]
+    fn merges_coprime_moduli() {
+        let congruences = [
+            Congruence::new(2, 3),
+            Congruence::new(3, 5),
+            Congruence::new(2, 7),
+        ];
+
+        assert_eq!(crt(&congruences), Some(Congruence::new(23, 105)));
+    }
+
+    #[test]
+    fn inverts_only_coprime_values() {
+        assert_eq!(mod_inverse(3, 7), Some(5));
+        assert_eq!(mod_inverse(-3, 7), Some(2));
+        assert_eq!(mod_inverse(4, 6), None);
+    }
+
+    #[test]
+    fn extended_gcd_gives_bezout_coefficients() {
+        let (g, x, y) = egcd(240, 46);
+
+        assert_eq!(g, 2);
+        assert_eq!(240 * x + 46 * y, 2);
+    }
+}